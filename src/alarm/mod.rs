@@ -0,0 +1,509 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::rc::Rc;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+use evalexpr::*;
+use log::{info, warn};
+
+use crate::pubsub::topic_matcher::{expand_captures, TopicPattern};
+use crate::pubsub::{payload_as_f64, payload_display};
+use crate::widget::notify::Notifier;
+
+// alarms that were cleared and acknowledged, kept for the Alarm widgets
+const MAX_HISTORY: usize = 100;
+// conditions on age and pending delays are evaluated this often
+const CHECK_PERIOD: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl FromStr for Severity {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "info" => Ok(Severity::Info),
+            "warning" => Ok(Severity::Warning),
+            "critical" => Ok(Severity::Critical),
+            _ => Err(format!("Invalid severity {}, expected info, warning or critical", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Severity::Info => "INFO",
+            Severity::Warning => "WARNING",
+            Severity::Critical => "CRITICAL",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A rule from a <Rule> element of <Alarms>. The condition is an expression
+/// on `value` (the payload as number), `text` (the payload as shown by
+/// payload_display) and `age` (seconds since the last message), such as
+/// "value > 80" or "age > 5". It must hold for delay msec before the alarm
+/// is raised, and a raised alarm only clears once the condition is false for
+/// every value within hysteresis of the current one.
+#[derive(Debug, Clone)]
+pub struct AlarmRule {
+    pub name: String,
+    pub topic: TopicPattern,
+    pub severity: Severity,
+    pub message: String,
+    pub hysteresis: f64,
+    pub delay: Duration,
+    condition: Node,
+}
+
+impl AlarmRule {
+    pub fn from_attrs(attrs: &BTreeMap<String, String>) -> Result<Self, String> {
+        let src = attrs.get("src").ok_or("Alarm rule without src")?;
+        let condition = attrs.get("condition").ok_or("Alarm rule without condition")?;
+        let parse = |name: &str| -> Result<Option<f64>, String> {
+            attrs
+                .get(name)
+                .map(|v| v.parse::<f64>().map_err(|e| format!("Invalid {} {} : {}", name, v, e)))
+                .transpose()
+        };
+        Ok(Self {
+            name: attrs
+                .get("name")
+                .cloned()
+                .unwrap_or_else(|| format!("{} {}", src, condition)),
+            topic: TopicPattern::compile(src)?,
+            severity: attrs.get("severity").map_or(Ok(Severity::Warning), |s| s.parse())?,
+            message: attrs.get("message").cloned().unwrap_or_else(|| condition.clone()),
+            hysteresis: parse("hysteresis")?.unwrap_or(0.0).abs(),
+            delay: Duration::from_millis(parse("delay")?.unwrap_or(0.0).max(0.0) as u64),
+            condition: build_operator_tree(condition)
+                .map_err(|e| format!("Invalid condition {} : {}", condition, e))?,
+        })
+    }
+
+    /// The rules of the <Alarms> element, names must be unique as alarms are
+    /// keyed on them.
+    pub fn from_rules(rules: &[BTreeMap<String, String>]) -> Result<Vec<Self>, String> {
+        let mut parsed: Vec<Self> = Vec::new();
+        for attrs in rules {
+            let rule = Self::from_attrs(attrs)?;
+            if parsed.iter().any(|r| r.name == rule.name) {
+                return Err(format!("Duplicate alarm rule name {}", rule.name));
+            }
+            parsed.push(rule);
+        }
+        Ok(parsed)
+    }
+
+    /// The condition for the last message of `watch`, with `value` moved by `margin`.
+    fn holds(&self, watch: &Watch, margin: f64, now: Instant) -> bool {
+        let mut context = HashMapContext::new();
+        if let Some(value) = watch.value {
+            let _ = context.set_value("value".into(), Value::Float(value + margin));
+        }
+        let _ = context.set_value("text".into(), Value::String(watch.text.clone()));
+        let age = now.saturating_duration_since(watch.last_update).as_secs_f64();
+        let _ = context.set_value("age".into(), Value::Float(age));
+        // a missing value, as for a text payload, doesn't raise anything
+        self.condition.eval_boolean_with_context(&context).unwrap_or(false)
+    }
+}
+
+/// Rule name and topic, one alarm per topic matched by a wildcard rule.
+pub type AlarmKey = (String, String);
+
+#[derive(Debug, Clone)]
+pub struct Alarm {
+    pub rule: String,
+    pub topic: String,
+    pub severity: Severity,
+    pub message: String,
+    pub value: String,
+    pub raised: DateTime<Local>,
+    pub acknowledged: Option<DateTime<Local>>,
+    pub cleared: Option<DateTime<Local>>,
+}
+
+impl Alarm {
+    pub fn key(&self) -> AlarmKey {
+        (self.rule.clone(), self.topic.clone())
+    }
+}
+
+/// The last message of a topic as seen by one rule.
+#[derive(Debug)]
+struct Watch {
+    value: Option<f64>,
+    text: String,
+    captures: Vec<String>,
+    last_update: Instant,
+    // since when the condition holds without the alarm raised yet
+    pending: Option<Instant>,
+    active: bool,
+}
+
+impl Watch {
+    fn new(now: Instant) -> Self {
+        Self {
+            value: None,
+            text: String::new(),
+            captures: Vec::new(),
+            last_update: now,
+            pending: None,
+            active: false,
+        }
+    }
+}
+
+/// Evaluates the alarm rules against the messages seen by the Dispatcher and
+/// keeps the alarms until they are both cleared and acknowledged, after which
+/// they move to the history. A raised alarm is signalled by the notifier
+/// until it is acknowledged.
+pub struct AlarmManager {
+    rules: Vec<AlarmRule>,
+    watches: BTreeMap<AlarmKey, Watch>,
+    alarms: Vec<Alarm>,
+    history: VecDeque<Alarm>,
+    last_check: Instant,
+    generation: u64,
+    notifier: Option<Rc<RefCell<Notifier>>>,
+}
+
+impl Default for AlarmManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AlarmManager {
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            watches: BTreeMap::new(),
+            alarms: Vec::new(),
+            history: VecDeque::new(),
+            last_check: Instant::now(),
+            generation: 0,
+            notifier: None,
+        }
+    }
+
+    pub fn set_notifier(&mut self, notifier: Rc<RefCell<Notifier>>) {
+        self.notifier = Some(notifier);
+    }
+
+    /// Installs the rules of a (re)loaded config. Alarms of rules that are
+    /// gone or no longer match their topic are cleared, the others keep
+    /// their state.
+    pub fn set_rules(&mut self, rules: Vec<AlarmRule>) {
+        let now = Instant::now();
+        let covered = |(name, topic): &AlarmKey| {
+            rules.iter().any(|r| &r.name == name && r.topic.matches(topic))
+        };
+        self.watches.retain(|key, _| covered(key));
+        // a topic that never publishes must still be able to time out
+        for rule in rules.iter() {
+            if let TopicPattern::Exact(topic) = &rule.topic {
+                self.watches
+                    .entry((rule.name.clone(), topic.clone()))
+                    .or_insert_with(|| Watch::new(now));
+            }
+        }
+        let gone: Vec<AlarmKey> = self
+            .alarms
+            .iter()
+            .filter(|a| !covered(&a.key()))
+            .map(|a| a.key())
+            .collect();
+        info!("Loaded {} alarm rules", rules.len());
+        self.rules = rules;
+        for key in gone {
+            self.clear(&key);
+            if let Some(notifier) = self.notifier.as_ref() {
+                notifier.borrow_mut().clear(&alarm_source(&key));
+            }
+        }
+        self.generation += 1;
+    }
+
+    /// Changes on every raise, clear and acknowledge, for the widgets to
+    /// know when to refresh.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Alarms not yet both cleared and acknowledged, in the order raised.
+    pub fn alarms(&self) -> &[Alarm] {
+        &self.alarms
+    }
+
+    /// The finished alarms, most recent last.
+    pub fn history(&self) -> &VecDeque<Alarm> {
+        &self.history
+    }
+
+    pub fn on_message(&mut self, topic: &str, payload: &Vec<u8>) {
+        let now = Instant::now();
+        for idx in 0..self.rules.len() {
+            let Some(captures) = self.rules[idx].topic.captures(topic) else {
+                continue;
+            };
+            let key = (self.rules[idx].name.clone(), topic.to_string());
+            let watch = self.watches.entry(key.clone()).or_insert_with(|| Watch::new(now));
+            watch.value = payload_as_f64(payload).ok();
+            watch.text = payload_display(payload);
+            watch.captures = captures;
+            watch.last_update = now;
+            self.evaluate(idx, &key, now);
+        }
+    }
+
+    /// Re-evaluates everything for the conditions on age and the delays,
+    /// does nothing when called more often than CHECK_PERIOD.
+    pub fn check(&mut self, now: Instant) {
+        if now.saturating_duration_since(self.last_check) < CHECK_PERIOD {
+            return;
+        }
+        self.last_check = now;
+        let keys: Vec<AlarmKey> = self.watches.keys().cloned().collect();
+        for key in keys {
+            if let Some(idx) = self.rules.iter().position(|r| r.name == key.0) {
+                self.evaluate(idx, &key, now);
+            }
+        }
+    }
+
+    /// Acknowledges the alarm, returns it when it was waiting for that.
+    pub fn acknowledge(&mut self, key: &AlarmKey) -> Option<Alarm> {
+        let idx = self
+            .alarms
+            .iter()
+            .position(|a| &a.key() == key && a.acknowledged.is_none())?;
+        self.alarms[idx].acknowledged = Some(Local::now());
+        let alarm = self.alarms[idx].clone();
+        info!("Alarm {} on {} acknowledged", alarm.rule, alarm.topic);
+        if let Some(notifier) = self.notifier.as_ref() {
+            notifier.borrow_mut().clear(&alarm_source(key));
+        }
+        if alarm.cleared.is_some() {
+            self.retire(idx);
+        }
+        self.generation += 1;
+        Some(alarm)
+    }
+
+    fn evaluate(&mut self, idx: usize, key: &AlarmKey, now: Instant) {
+        let rule = &self.rules[idx];
+        let Some(watch) = self.watches.get_mut(key) else {
+            return;
+        };
+        if watch.active {
+            let h = rule.hysteresis;
+            if !rule.holds(watch, h, now) && !rule.holds(watch, -h, now) {
+                watch.active = false;
+                self.clear(key);
+            }
+            return;
+        }
+        if !rule.holds(watch, 0.0, now) {
+            watch.pending = None;
+            return;
+        }
+        let since = *watch.pending.get_or_insert(now);
+        if now.saturating_duration_since(since) < rule.delay {
+            return;
+        }
+        watch.pending = None;
+        watch.active = true;
+        let alarm = Alarm {
+            rule: rule.name.clone(),
+            topic: key.1.clone(),
+            severity: rule.severity,
+            message: expand_captures(&rule.message, &watch.captures),
+            value: watch.text.clone(),
+            raised: Local::now(),
+            acknowledged: None,
+            cleared: None,
+        };
+        warn!("Alarm {} {} on {} : {}", alarm.severity, alarm.rule, alarm.topic, alarm.message);
+        if let Some(notifier) = self.notifier.as_ref() {
+            notifier
+                .borrow_mut()
+                .notify(&alarm_source(key), alarm.severity, &alarm.message);
+        }
+        // raised again before it was acknowledged, it needs a new acknowledge
+        self.alarms.retain(|a| a.key() != alarm.key());
+        self.alarms.push(alarm);
+        self.generation += 1;
+    }
+
+    fn clear(&mut self, key: &AlarmKey) {
+        let Some(idx) = self
+            .alarms
+            .iter()
+            .position(|a| &a.key() == key && a.cleared.is_none())
+        else {
+            return;
+        };
+        self.alarms[idx].cleared = Some(Local::now());
+        info!("Alarm {} on {} cleared", key.0, key.1);
+        if self.alarms[idx].acknowledged.is_some() {
+            self.retire(idx);
+        }
+        self.generation += 1;
+    }
+
+    fn retire(&mut self, idx: usize) {
+        self.history.push_back(self.alarms.remove(idx));
+        while self.history.len() > MAX_HISTORY {
+            self.history.pop_front();
+        }
+    }
+}
+
+fn alarm_source((rule, topic): &AlarmKey) -> String {
+    format!("alarm {} {}", rule, topic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pubsub::payload_encode;
+
+    fn rule(attrs: &[(&str, &str)]) -> AlarmRule {
+        let attrs: BTreeMap<String, String> = attrs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        AlarmRule::from_attrs(&attrs).unwrap()
+    }
+
+    fn manager(rules: Vec<AlarmRule>) -> AlarmManager {
+        let mut manager = AlarmManager::new();
+        manager.set_rules(rules);
+        manager
+    }
+
+    fn temp_rule() -> AlarmRule {
+        rule(&[("name", "hot"), ("src", "src/+/temp"), ("condition", "value > 80"), ("hysteresis", "5")])
+    }
+
+    fn key(topic: &str) -> AlarmKey {
+        ("hot".to_string(), topic.to_string())
+    }
+
+    #[test]
+    fn raise_and_clear() {
+        let mut manager = manager(vec![temp_rule()]);
+        manager.on_message("src/esp1/temp", &payload_encode(70.0));
+        assert!(manager.alarms().is_empty());
+        manager.on_message("src/esp1/temp", &payload_encode(90.0));
+        assert_eq!(manager.alarms().len(), 1);
+        let alarm = &manager.alarms()[0];
+        assert_eq!(alarm.key(), key("src/esp1/temp"));
+        assert_eq!(alarm.severity, Severity::Warning);
+        assert!(alarm.cleared.is_none());
+        manager.on_message("src/esp1/temp", &payload_encode(60.0));
+        // cleared but still waiting for the acknowledge
+        assert_eq!(manager.alarms().len(), 1);
+        assert!(manager.alarms()[0].cleared.is_some());
+    }
+
+    #[test]
+    fn one_alarm_per_topic() {
+        let mut manager = manager(vec![temp_rule()]);
+        manager.on_message("src/esp1/temp", &payload_encode(90.0));
+        manager.on_message("src/esp2/temp", &payload_encode(90.0));
+        manager.on_message("src/esp1/temp", &payload_encode(95.0));
+        assert_eq!(manager.alarms().len(), 2);
+    }
+
+    #[test]
+    fn hysteresis() {
+        let mut manager = manager(vec![temp_rule()]);
+        manager.on_message("src/esp1/temp", &payload_encode(90.0));
+        manager.on_message("src/esp1/temp", &payload_encode(78.0));
+        assert!(manager.alarms()[0].cleared.is_none());
+        manager.on_message("src/esp1/temp", &payload_encode(74.0));
+        assert!(manager.alarms()[0].cleared.is_some());
+    }
+
+    #[test]
+    fn acknowledge() {
+        let mut manager = manager(vec![temp_rule()]);
+        manager.on_message("src/esp1/temp", &payload_encode(90.0));
+        let generation = manager.generation();
+        assert!(manager.acknowledge(&key("src/esp1/temp")).is_some());
+        assert!(manager.generation() > generation);
+        // still active, only acknowledged once
+        assert_eq!(manager.alarms().len(), 1);
+        assert!(manager.acknowledge(&key("src/esp1/temp")).is_none());
+        manager.on_message("src/esp1/temp", &payload_encode(60.0));
+        assert!(manager.alarms().is_empty());
+        assert_eq!(manager.history().len(), 1);
+    }
+
+    #[test]
+    fn acknowledge_after_clear() {
+        let mut manager = manager(vec![temp_rule()]);
+        manager.on_message("src/esp1/temp", &payload_encode(90.0));
+        manager.on_message("src/esp1/temp", &payload_encode(60.0));
+        assert!(manager.history().is_empty());
+        manager.acknowledge(&key("src/esp1/temp"));
+        assert!(manager.alarms().is_empty());
+        assert_eq!(manager.history().len(), 1);
+    }
+
+    #[test]
+    fn delay() {
+        let mut manager = manager(vec![rule(&[
+            ("name", "hot"),
+            ("src", "src/esp1/temp"),
+            ("condition", "value > 80"),
+            ("delay", "2000"),
+        ])]);
+        let now = Instant::now();
+        manager.on_message("src/esp1/temp", &payload_encode(90.0));
+        assert!(manager.alarms().is_empty());
+        manager.check(now + Duration::from_secs(1));
+        assert!(manager.alarms().is_empty());
+        manager.check(now + Duration::from_secs(3));
+        assert_eq!(manager.alarms().len(), 1);
+    }
+
+    #[test]
+    fn text_payload_raises_nothing() {
+        let mut manager = manager(vec![temp_rule()]);
+        manager.on_message("src/esp1/temp", &payload_encode("hot"));
+        assert!(manager.alarms().is_empty());
+    }
+
+    #[test]
+    fn changed_topic_resets_alarm() {
+        let mut manager = manager(vec![temp_rule()]);
+        manager.on_message("src/esp1/temp", &payload_encode(90.0));
+        manager.set_rules(vec![rule(&[("name", "hot"), ("src", "src/+/hum"), ("condition", "value > 80")])]);
+        assert!(manager.alarms()[0].cleared.is_some());
+        assert!(manager.watches.is_empty());
+    }
+
+    #[test]
+    fn duplicate_rule_names() {
+        let attrs = |src: &str| -> BTreeMap<String, String> {
+            [("name", "hot"), ("src", src), ("condition", "value > 80")]
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        assert!(AlarmRule::from_rules(&[attrs("a"), attrs("b")]).is_err());
+        assert_eq!(AlarmRule::from_rules(&[attrs("a")]).unwrap().len(), 1);
+    }
+}
@@ -9,6 +9,8 @@ use std::{collections::BTreeMap, str::FromStr};
 
 use serde_xml_rs::from_str;
 
+use crate::widget::Context;
+
 #[derive(Debug,Clone)]
 pub struct WidgetParams {
     pub name: String,
@@ -37,92 +39,431 @@ pub struct WidgetParams {
     pub children: Vec<WidgetParams>,
     pub max_samples: Option<usize>,
     pub max_timespan: Option<i32>,
+    pub key: Option<String>,
+    pub rotate: Option<i32>,
+    pub cols: Option<i32>,
+    pub rows: Option<i32>,
+    pub col: Option<i32>,
+    pub row: Option<i32>,
+    pub colspan: Option<i32>,
+    pub rowspan: Option<i32>,
+    pub theme: Option<String>,
+    pub scheme: Option<String>,
+    pub bg: Option<String>,
+    pub fg: Option<String>,
+    pub font: Option<String>,
+    pub frame: Option<String>,
+    pub path: Vec<usize>,
+    pub grid_cell: Option<Rect>,
+    pub fps: Option<i32>,
+    pub stats: Option<bool>,
+    pub confirm: Option<String>,
+    pub step: Option<f64>,
+    pub value_type: Option<String>,
+    pub history: Option<usize>,
+    pub target: Option<String>,
+    pub offset: Option<f64>,
+    pub digits: Option<usize>,
+    pub decimals: Option<usize>,
+    pub segments: Option<usize>,
+    pub amber: Option<f64>,
+    pub red: Option<f64>,
+    pub images: Option<String>,
+    pub src_y: Option<String>,
+    pub fields: Option<String>,
+    pub xmin: Option<f64>,
+    pub xmax: Option<f64>,
+    pub waypoints: Option<String>,
+    pub sort: Option<String>,
+    pub lines: Option<usize>,
+    pub notify: Option<String>,
+    pub blink: Option<String>,
+    pub sound: Option<String>,
+    pub command: Option<String>,
+    pub value: Option<String>,
+    pub delay: Option<i32>,
+    pub wait: Option<String>,
+}
+
+pub const TAB_HEIGHT: i32 = 25;
+
+/// Dashboard children that configure the application instead of placing widgets.
+const SETTINGS_TAGS: [&str; 3] = ["Palette", "Alarms", "Notify"];
+
+/// Attributes that can be changed at runtime from the property editor.
+pub const EDITABLE_ATTRS: [&str; 51] = [
+    "label", "src", "dst", "min", "max", "timeout", "msec", "prefix", "suffix", "unit",
+    "on", "off", "pressed", "released", "ok", "nok", "image", "url", "samples",
+    "timespan", "text_size", "bg", "fg", "font", "frame", "confirm", "step", "type",
+    "history", "target", "offset", "digits", "decimals", "segments", "amber", "red",
+    "images", "src_y", "fields", "xmin", "xmax", "waypoints", "sort", "lines", "notify",
+    "blink", "sound", "command", "value", "delay", "wait",
+];
+
+fn parse_attr<T: FromStr>(attr_name: &str, attr_value: &str) -> Result<T, String> {
+    FromStr::from_str(attr_value)
+        .map_err(|_| format!("Invalid value '{}' for attribute {}", attr_value, attr_name))
 }
 
 pub fn get_widget_params(rect:Rect,element: &Element) -> Result<WidgetParams, String> {
     let mut widget_params = WidgetParams::new(String::from(element.name()),rect);
     for (attr_name,attr_value) in element.attrs(){
+        widget_params.set_attr(attr_name, attr_value)?;
+    }
+    // the steps of a MacroButton and the bars of a BarChart
+    for step in element.children().filter(|c| c.name() == "Step" || c.name() == "Bar") {
+        widget_params.children.push(get_widget_params(rect, step)?);
+    }
+    if widget_params.name == "Grid" {
+        // without explicit size a grid takes cols x rows cells of the context grid
+        let ctx = Context::new();
+        if widget_params.width.is_none() {
+            widget_params.width = widget_params.cols.map(|c| c * ctx.grid_width);
+            widget_params.width.map(|w| widget_params.rect.w = w);
+        }
+        if widget_params.height.is_none() {
+            widget_params.height = widget_params.rows.map(|r| r * ctx.grid_height);
+            widget_params.height.map(|h| widget_params.rect.h = h);
+        }
+    }
+    Ok(widget_params)
+}
+
+impl WidgetParams {
+    /// Sets a field from its config attribute name.
+    pub fn set_attr(&mut self, attr_name: &str, attr_value: &str) -> Result<(), String> {
         match attr_name {
             "label" => {
-                widget_params.label = Some(String::from(attr_value));
+                self.label = Some(String::from(attr_value));
             }
             "src" => {
-                widget_params.src_topic = Some(String::from(attr_value));
+                self.src_topic = Some(String::from(attr_value));
             }
             "dst" => {
-                widget_params.dst_topic = Some(String::from(attr_value));
+                self.dst_topic = Some(String::from(attr_value));
             }
             "pressed" => {
-                widget_params.pressed = Some(String::from(attr_value));
+                self.pressed = Some(String::from(attr_value));
             }
             "released" => {
-                widget_params.released = Some(String::from(attr_value));
+                self.released = Some(String::from(attr_value));
             }
             "prefix" => {
-                widget_params.prefix = Some(String::from(attr_value));
+                self.prefix = Some(String::from(attr_value));
             }
             "suffix" => {
-                widget_params.suffix = Some(String::from(attr_value));
+                self.suffix = Some(String::from(attr_value));
             }
             "unit" => {
-                widget_params.unit = Some(String::from(attr_value));
+                self.unit = Some(String::from(attr_value));
             }
             "image" => {
-                widget_params.image = Some(String::from(attr_value));
+                self.image = Some(String::from(attr_value));
             }
             "url" => {
-                widget_params.url = Some(String::from(attr_value));
+                self.url = Some(String::from(attr_value));
             }
             "ok" => {
-                widget_params.ok = Some(String::from(attr_value));
+                self.ok = Some(String::from(attr_value));
             }
             "nok" => {
-                widget_params.ko = Some(String::from(attr_value));
+                self.ko = Some(String::from(attr_value));
             }
             "h" => {
-                widget_params.rect.h = FromStr::from_str(attr_value).unwrap();
-                widget_params.height = Some(FromStr::from_str(attr_value).unwrap());
+                self.rect.h = parse_attr(attr_name, attr_value)?;
+                self.height = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "x" => {
+                self.rect.x = parse_attr(attr_name, attr_value)?;
+            }
+            "y" => {
+                self.rect.y = parse_attr(attr_name, attr_value)?;
             }
             "w" => {
-                widget_params.rect.w = FromStr::from_str(attr_value).unwrap();
-                widget_params.width = Some(FromStr::from_str(attr_value).unwrap());
+                self.rect.w = parse_attr(attr_name, attr_value)?;
+                self.width = Some(parse_attr(attr_name, attr_value)?);
             }
             "min" => {
-                widget_params.min = Some(FromStr::from_str(attr_value).unwrap());
+                self.min = Some(parse_attr(attr_name, attr_value)?);
             }
             "max" => {
-                widget_params.max = Some(FromStr::from_str(attr_value).unwrap());
+                self.max = Some(parse_attr(attr_name, attr_value)?);
             }
             "timeout" => {
-                widget_params.timeout = Some(FromStr::from_str(attr_value).unwrap());
+                self.timeout = Some(parse_attr(attr_name, attr_value)?);
             }
             "msec" => {
-                widget_params.msec = Some(FromStr::from_str(attr_value).unwrap());
+                self.msec = Some(parse_attr(attr_name, attr_value)?);
             }
             "on" => {
-                widget_params.on = Some(String::from(attr_value));
+                self.on = Some(String::from(attr_value));
             }
             "off" => {
-                widget_params.off = Some(String::from(attr_value));
+                self.off = Some(String::from(attr_value));
             }
             "text_size" => {
-                widget_params.text_size = Some(FromStr::from_str(attr_value).unwrap());
+                self.text_size = Some(parse_attr(attr_name, attr_value)?);
             }
             "samples" => {
-                widget_params.max_samples = Some(FromStr::from_str(attr_value).unwrap());
+                self.max_samples = Some(parse_attr(attr_name, attr_value)?);
             }
             "timespan" => {
-                widget_params.max_timespan = Some(FromStr::from_str(attr_value).unwrap());
+                self.max_timespan = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "key" => {
+                self.key = Some(String::from(attr_value));
+            }
+            "rotate" => {
+                self.rotate = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "cols" => {
+                self.cols = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "rows" => {
+                self.rows = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "col" => {
+                self.col = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "row" => {
+                self.row = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "colspan" => {
+                self.colspan = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "rowspan" => {
+                self.rowspan = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "theme" => {
+                self.theme = Some(String::from(attr_value));
+            }
+            "scheme" => {
+                self.scheme = Some(String::from(attr_value));
+            }
+            "bg" => {
+                self.bg = Some(String::from(attr_value));
+            }
+            "fg" => {
+                self.fg = Some(String::from(attr_value));
+            }
+            "font" => {
+                self.font = Some(String::from(attr_value));
+            }
+            "font_size" => {
+                self.text_size = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "frame" => {
+                self.frame = Some(String::from(attr_value));
+            }
+            "fps" => {
+                self.fps = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "stats" => {
+                self.stats = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "confirm" => {
+                self.confirm = Some(String::from(attr_value));
+            }
+            "step" => {
+                self.step = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "type" => {
+                self.value_type = Some(String::from(attr_value));
+            }
+            "history" => {
+                self.history = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "target" => {
+                self.target = Some(String::from(attr_value));
+            }
+            "offset" => {
+                self.offset = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "digits" => {
+                self.digits = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "decimals" => {
+                self.decimals = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "segments" => {
+                self.segments = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "amber" => {
+                self.amber = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "red" => {
+                self.red = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "images" => {
+                self.images = Some(String::from(attr_value));
+            }
+            "src_y" => {
+                self.src_y = Some(String::from(attr_value));
+            }
+            "fields" => {
+                self.fields = Some(String::from(attr_value));
+            }
+            "xmin" => {
+                self.xmin = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "xmax" => {
+                self.xmax = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "waypoints" => {
+                self.waypoints = Some(String::from(attr_value));
+            }
+            "sort" => {
+                self.sort = Some(String::from(attr_value));
+            }
+            "lines" => {
+                self.lines = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "notify" => {
+                self.notify = Some(String::from(attr_value));
+            }
+            "blink" => {
+                self.blink = Some(String::from(attr_value));
+            }
+            "sound" => {
+                self.sound = Some(String::from(attr_value));
+            }
+            "command" => {
+                self.command = Some(String::from(attr_value));
+            }
+            "value" => {
+                self.value = Some(String::from(attr_value));
+            }
+            "delay" => {
+                self.delay = Some(parse_attr(attr_name, attr_value)?);
+            }
+            "wait" => {
+                self.wait = Some(String::from(attr_value));
             }
             _ => {
                 error!("Unknown attribute: {}", attr_value);
             }
         };
+        Ok(())
+    }
+
+    /// Gets a field by its config attribute name, as written in the config.
+    pub fn get_attr(&self, attr_name: &str) -> Option<String> {
+        match attr_name {
+            "label" => self.label.clone(),
+            "src" => self.src_topic.clone(),
+            "dst" => self.dst_topic.clone(),
+            "pressed" => self.pressed.clone(),
+            "released" => self.released.clone(),
+            "prefix" => self.prefix.clone(),
+            "suffix" => self.suffix.clone(),
+            "unit" => self.unit.clone(),
+            "image" => self.image.clone(),
+            "url" => self.url.clone(),
+            "ok" => self.ok.clone(),
+            "nok" => self.ko.clone(),
+            "min" => self.min.map(|v| v.to_string()),
+            "max" => self.max.map(|v| v.to_string()),
+            "timeout" => self.timeout.map(|v| v.to_string()),
+            "msec" => self.msec.map(|v| v.to_string()),
+            "on" => self.on.clone(),
+            "off" => self.off.clone(),
+            "text_size" => self.text_size.map(|v| v.to_string()),
+            "samples" => self.max_samples.map(|v| v.to_string()),
+            "timespan" => self.max_timespan.map(|v| v.to_string()),
+            "bg" => self.bg.clone(),
+            "fg" => self.fg.clone(),
+            "font" => self.font.clone(),
+            "frame" => self.frame.clone(),
+            "confirm" => self.confirm.clone(),
+            "step" => self.step.map(|v| v.to_string()),
+            "type" => self.value_type.clone(),
+            "history" => self.history.map(|v| v.to_string()),
+            "target" => self.target.clone(),
+            "offset" => self.offset.map(|v| v.to_string()),
+            "digits" => self.digits.map(|v| v.to_string()),
+            "decimals" => self.decimals.map(|v| v.to_string()),
+            "segments" => self.segments.map(|v| v.to_string()),
+            "amber" => self.amber.map(|v| v.to_string()),
+            "red" => self.red.map(|v| v.to_string()),
+            "images" => self.images.clone(),
+            "src_y" => self.src_y.clone(),
+            "fields" => self.fields.clone(),
+            "xmin" => self.xmin.map(|v| v.to_string()),
+            "xmax" => self.xmax.map(|v| v.to_string()),
+            "waypoints" => self.waypoints.clone(),
+            "sort" => self.sort.clone(),
+            "lines" => self.lines.map(|v| v.to_string()),
+            "notify" => self.notify.clone(),
+            "blink" => self.blink.clone(),
+            "sound" => self.sound.clone(),
+            "command" => self.command.clone(),
+            "value" => self.value.clone(),
+            "delay" => self.delay.map(|v| v.to_string()),
+            "wait" => self.wait.clone(),
+            _ => None,
+        }
+    }
+
+    /// Clears a field by its config attribute name, as if the attribute
+    /// was left out of the config.
+    pub fn unset_attr(&mut self, attr_name: &str) -> Result<(), String> {
+        match attr_name {
+            "label" => self.label = None,
+            "src" => self.src_topic = None,
+            "dst" => self.dst_topic = None,
+            "pressed" => self.pressed = None,
+            "released" => self.released = None,
+            "prefix" => self.prefix = None,
+            "suffix" => self.suffix = None,
+            "unit" => self.unit = None,
+            "image" => self.image = None,
+            "url" => self.url = None,
+            "ok" => self.ok = None,
+            "nok" => self.ko = None,
+            "min" => self.min = None,
+            "max" => self.max = None,
+            "timeout" => self.timeout = None,
+            "msec" => self.msec = None,
+            "on" => self.on = None,
+            "off" => self.off = None,
+            "text_size" => self.text_size = None,
+            "samples" => self.max_samples = None,
+            "timespan" => self.max_timespan = None,
+            "bg" => self.bg = None,
+            "fg" => self.fg = None,
+            "font" => self.font = None,
+            "frame" => self.frame = None,
+            "confirm" => self.confirm = None,
+            "step" => self.step = None,
+            "type" => self.value_type = None,
+            "history" => self.history = None,
+            "target" => self.target = None,
+            "offset" => self.offset = None,
+            "digits" => self.digits = None,
+            "decimals" => self.decimals = None,
+            "segments" => self.segments = None,
+            "amber" => self.amber = None,
+            "red" => self.red = None,
+            "images" => self.images = None,
+            "src_y" => self.src_y = None,
+            "fields" => self.fields = None,
+            "xmin" => self.xmin = None,
+            "xmax" => self.xmax = None,
+            "waypoints" => self.waypoints = None,
+            "sort" => self.sort = None,
+            "lines" => self.lines = None,
+            "notify" => self.notify = None,
+            "blink" => self.blink = None,
+            "sound" => self.sound = None,
+            "command" => self.command = None,
+            "value" => self.value = None,
+            "delay" => self.delay = None,
+            "wait" => self.wait = None,
+            _ => return Err(format!("Unknown attribute {}", attr_name)),
+        }
+        Ok(())
     }
-    Ok(widget_params)
-}
 
-impl WidgetParams {
     pub fn new(name: String,rect : Rect) -> Self {
         Self {
             name,
@@ -151,16 +492,62 @@ impl WidgetParams {
             children: Vec::new(),
             max_samples: None,
             max_timespan: None,
+            key: None,
+            rotate: None,
+            cols: None,
+            rows: None,
+            col: None,
+            row: None,
+            colspan: None,
+            rowspan: None,
+            theme: None,
+            scheme: None,
+            bg: None,
+            fg: None,
+            font: None,
+            frame: None,
+            path: Vec::new(),
+            grid_cell: None,
+            fps: None,
+            stats: None,
+            confirm: None,
+            step: None,
+            value_type: None,
+            history: None,
+            target: None,
+            offset: None,
+            digits: None,
+            decimals: None,
+            segments: None,
+            amber: None,
+            red: None,
+            images: None,
+            src_y: None,
+            fields: None,
+            xmin: None,
+            xmax: None,
+            waypoints: None,
+            sort: None,
+            lines: None,
+            notify: None,
+            blink: None,
+            sound: None,
+            command: None,
+            value: None,
+            delay: None,
+            wait: None,
         }
     }
 }
 
-pub fn load_xml_file(path: &str) -> Result<Element, minidom::Error> {
-    let mut file = File::open(path).expect(std::format!("Unable to open file {} ", path).as_str());
+pub fn load_xml_file(path: &str) -> Result<Element, String> {
+    let mut file = File::open(path).map_err(|e| format!("Unable to open file {} : {}", path, e))?;
     let mut contents = String::new();
     file.read_to_string(&mut contents)
-        .expect("Unable to read file ");
-    contents.parse::<Element>()
+        .map_err(|e| format!("Unable to read file {} : {}", path, e))?;
+    contents
+        .parse::<Element>()
+        .map_err(|e| format!("Invalid XML in {} : {}", path, e))
 }
 
 pub fn load_dashboard(root:&Element ) -> Result<Vec<WidgetParams>, String> {
@@ -169,12 +556,87 @@ pub fn load_dashboard(root:&Element ) -> Result<Vec<WidgetParams>, String> {
     if cfg.name != "Dashboard" {
         return Err("Invalid config file. Missing Dashboard tag.".to_string());
     }
-    let mut rect = Rect::new(0, 0, cfg.width.unwrap_or(1025), cfg.height.unwrap_or(769));
+    let rect = Rect::new(0, 0, cfg.width.unwrap_or(1025), cfg.height.unwrap_or(769));
     cfg.rect = rect;
-    for child_element in root.children() {
+    // a tab bar is only drawn when at least one page is a Tab
+    let header = if root.children().any(|c| c.name() == "Tab") {
+        TAB_HEIGHT
+    } else {
+        0
+    };
+    let mut page_elements = root
+        .children()
+        .enumerate()
+        .filter(|(_, c)| c.name() == "Tab" || c.name() == "Page")
+        .peekable();
+    if page_elements.peek().is_none() {
+        return load_children(rect, root, &[]);
+    }
+    // widgets next to the pages would have no page to live on
+    let stray: Vec<&str> = root
+        .children()
+        .map(|c| c.name())
+        .filter(|name| *name != "Tab" && *name != "Page" && !SETTINGS_TAGS.contains(name))
+        .collect();
+    if !stray.is_empty() {
+        return Err(format!(
+            "Dashboard mixes pages with other widgets ({}), move them into a Tab or Page",
+            stray.join(", ")
+        ));
+    }
+    for (idx, page_element) in page_elements {
+        let page_rect = Rect::new(rect.x, rect.y + header, rect.w, rect.h - header);
+        let mut page = get_widget_params(page_rect, page_element)?;
+        info!("Loading page {}", page.label.as_ref().unwrap_or(&page.name));
+        page.path = vec![idx];
+        page.children = load_children(page_rect, page_element, &page.path)?;
+        widgets.push(page);
+    }
+    Ok(widgets)
+}
+
+/// Named colours from the <Palette> element of the Dashboard, unresolved.
+pub fn load_palette(root: &Element) -> BTreeMap<String, String> {
+    root.children()
+        .filter(|c| c.name() == "Palette")
+        .flat_map(|c| c.attrs())
+        .map(|(name, value)| (String::from(name), String::from(value)))
+        .collect()
+}
+
+/// Attributes of the <Rule> elements in the <Alarms> element of the
+/// Dashboard, one map per rule.
+pub fn load_alarm_rules(root: &Element) -> Vec<BTreeMap<String, String>> {
+    root.children()
+        .filter(|c| c.name() == "Alarms")
+        .flat_map(|c| c.children())
+        .filter(|c| c.name() == "Rule")
+        .map(element_attrs)
+        .collect()
+}
+
+/// Attributes of the <Notify> elements of the Dashboard, one map per severity.
+pub fn load_notify_actions(root: &Element) -> Vec<BTreeMap<String, String>> {
+    root.children()
+        .filter(|c| c.name() == "Notify")
+        .map(element_attrs)
+        .collect()
+}
+
+fn element_attrs(element: &Element) -> BTreeMap<String, String> {
+    element
+        .attrs()
+        .map(|(name, value)| (String::from(name), String::from(value)))
+        .collect()
+}
+
+fn load_children(rect: Rect, element: &Element, path: &[usize]) -> Result<Vec<WidgetParams>, String> {
+    let mut widgets: Vec<WidgetParams> = Vec::new();
+    let mut rect = rect;
+    for (idx, child_element) in element.children().enumerate().filter(|(_, c)| !SETTINGS_TAGS.contains(&c.name())) {
         let child = get_widget_params(rect,child_element)?;
         info!("Loading widget {}", child.name);
-        let mut sub_widgets = load_widgets(rect, child_element)?;
+        let mut sub_widgets = load_widgets(rect, child_element, &child_path(path, idx))?;
         widgets.append(&mut sub_widgets);
         if child.width.is_some() {
             rect.x += child.width.unwrap();
@@ -186,8 +648,17 @@ pub fn load_dashboard(root:&Element ) -> Result<Vec<WidgetParams>, String> {
     Ok(widgets)
 }
 
-fn load_widgets(rect: Rect, element: &Element) -> Result<Vec<WidgetParams>, String> {
-    let cfg = get_widget_params(rect,element)?;
+/// The path of a widget is the list of child indices from the Dashboard
+/// (or page) element down to its own element, used to write edits back.
+fn child_path(path: &[usize], idx: usize) -> Vec<usize> {
+    let mut path = path.to_vec();
+    path.push(idx);
+    path
+}
+
+fn load_widgets(rect: Rect, element: &Element, path: &[usize]) -> Result<Vec<WidgetParams>, String> {
+    let mut cfg = get_widget_params(rect,element)?;
+    cfg.path = path.to_vec();
     let mut widgets: Vec<WidgetParams> = Vec::new();
     let mut rect = cfg.rect;
 
@@ -202,9 +673,9 @@ fn load_widgets(rect: Rect, element: &Element) -> Result<Vec<WidgetParams>, Stri
     match cfg.name.as_str() {
         "Row" => {
             rect.h = cfg.height.unwrap_or(rect.h);
-            for child_element in element.children() {
+            for (idx, child_element) in element.children().enumerate() {
                 let child = get_widget_params(rect,child_element)?;
-                let mut sub_widgets = load_widgets(rect, child_element)?;
+                let mut sub_widgets = load_widgets(rect, child_element, &child_path(path, idx))?;
                 widgets.append(&mut sub_widgets);
                 rect.x += child.width.unwrap_or(0);
             }
@@ -212,13 +683,16 @@ fn load_widgets(rect: Rect, element: &Element) -> Result<Vec<WidgetParams>, Stri
         "Col" => {
             rect.w = cfg.width.unwrap_or(rect.w);
 
-            for child_element in element.children() {
+            for (idx, child_element) in element.children().enumerate() {
                 let child = get_widget_params(rect,child_element)?;
-                let mut sub_widgets = load_widgets(rect, child_element)?;
+                let mut sub_widgets = load_widgets(rect, child_element, &child_path(path, idx))?;
                 widgets.append(&mut sub_widgets);
                 rect.y += child.height.unwrap_or(0);
             }
         }
+        "Grid" => {
+            widgets = load_grid(&cfg, element, path)?;
+        }
         _ => {
             widgets.push(cfg.clone());
             rect.y = rect.y + cfg.height.unwrap_or(0);
@@ -229,6 +703,120 @@ fn load_widgets(rect: Rect, element: &Element) -> Result<Vec<WidgetParams>, Stri
     Ok(widgets)
 }
 
+/// Places the children of a Grid on its cells. A nested Grid fills the
+/// cells it spans in its parent.
+fn load_grid(cfg: &WidgetParams, element: &Element, path: &[usize]) -> Result<Vec<WidgetParams>, String> {
+    let rect = cfg.rect;
+    let ctx = Context::new();
+    let cols = cfg.cols.unwrap_or(rect.w / ctx.grid_width).max(1);
+    let rows = cfg.rows.unwrap_or(rect.h / ctx.grid_height).max(1);
+    let cell_w = rect.w / cols;
+    let cell_h = rect.h / rows;
+    if cell_w < 1 || cell_h < 1 {
+        return Err(format!(
+            "Grid of {}x{} px is too small for {} cols and {} rows",
+            rect.w, rect.h, cols, rows
+        ));
+    }
+    let mut widgets: Vec<WidgetParams> = Vec::new();
+    for (idx, child_element) in element.children().enumerate() {
+        let child = get_widget_params(rect, child_element)?;
+        // a pixel size is rounded up to whole cells
+        let colspan = child
+            .colspan
+            .or(child.width.map(|w| (w + cell_w - 1) / cell_w))
+            .unwrap_or(1)
+            .max(1);
+        let rowspan = child
+            .rowspan
+            .or(child.height.map(|h| (h + cell_h - 1) / cell_h))
+            .unwrap_or(1)
+            .max(1);
+        let (col, row) = (child.col.unwrap_or(0), child.row.unwrap_or(0));
+        if col < 0 || row < 0 || col + colspan > cols || row + rowspan > rows {
+            return Err(format!(
+                "{} at col {} row {} spanning {}x{} doesn't fit a grid of {} cols and {} rows",
+                child.name, col, row, colspan, rowspan, cols, rows
+            ));
+        }
+        let cell = Rect::new(
+            rect.x + col * cell_w,
+            rect.y + row * cell_h,
+            colspan * cell_w,
+            rowspan * cell_h,
+        );
+        let child_path = child_path(path, idx);
+        let mut sub_widgets = match child.name.as_str() {
+            "Grid" => {
+                let mut nested = child.clone();
+                nested.rect = cell;
+                load_grid(&nested, child_element, &child_path)?
+            }
+            "Row" | "Col" => load_widgets(cell, child_element, &child_path)?,
+            _ => {
+                let mut leaf = child.clone();
+                leaf.rect = cell;
+                leaf.path = child_path;
+                leaf.grid_cell = Some(Rect::new(rect.x, rect.y, cell_w, cell_h));
+                vec![leaf]
+            }
+        };
+        widgets.append(&mut sub_widgets);
+    }
+    Ok(widgets)
+}
+
+/// Writes the editable attributes of the given widgets back into the config
+/// file, and the geometry of those in `moved`. Widgets inside a Grid store
+/// their cell coordinates and spans, inside a Row or Col only their size so
+/// the layout stays relative.
+pub fn save_layout(path: &str, widgets: &[WidgetParams], moved: &[Vec<usize>]) -> Result<(), String> {
+    let mut root = load_xml_file(path)?;
+    let dashboard = root
+        .get_child_mut("Dashboard", "")
+        .ok_or("Dashboard section not found")?;
+    for widget in widgets {
+        let mut element = &mut *dashboard;
+        let mut parent = String::new();
+        for idx in widget.path.iter() {
+            parent = element.name().to_string();
+            element = element
+                .children_mut()
+                .nth(*idx)
+                .ok_or(format!("Invalid widget path {:?}", widget.path))?;
+        }
+        let r = widget.rect;
+        match widget.grid_cell {
+            _ if !moved.contains(&widget.path) => {}
+            Some(cell) => {
+                let (cell_w, cell_h) = (cell.w.max(1), cell.h.max(1));
+                element.set_attr("col", ((r.x - cell.x) / cell_w).to_string());
+                element.set_attr("row", ((r.y - cell.y) / cell_h).to_string());
+                element.set_attr("colspan", (r.w / cell_w).max(1).to_string());
+                element.set_attr("rowspan", (r.h / cell_h).max(1).to_string());
+            }
+            None if parent == "Row" || parent == "Col" => {
+                element.set_attr("w", r.w.to_string());
+                element.set_attr("h", r.h.to_string());
+            }
+            None => {
+                element.set_attr("x", r.x.to_string());
+                element.set_attr("y", r.y.to_string());
+                element.set_attr("w", r.w.to_string());
+                element.set_attr("h", r.h.to_string());
+            }
+        }
+        // an attribute cleared in the property editor is removed
+        for attr in EDITABLE_ATTRS {
+            element.set_attr(attr, widget.get_attr(attr));
+        }
+    }
+    let mut file = File::create(path).map_err(|e| e.to_string())?;
+    root.write_to(&mut file).map_err(|e| e.to_string())?;
+    info!("Saved {} widgets, {} moved, to {}", widgets.len(), moved.len(), path);
+    Ok(())
+}
+
 pub fn split_underscore(str: &String) -> (Option<&str>, Option<&str>) {
     let mut it = str.split("_");
     (it.next(), it.next())
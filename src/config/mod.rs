@@ -4,7 +4,7 @@ use std::fs::File;
 use std::io::Read;
 use log::{debug, error, info, trace, warn};
 
-mod file_change;
+pub(crate) mod file_change;
 pub(crate) mod file_xml;
 
 use file_change::FileChange;
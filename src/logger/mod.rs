@@ -1,10 +1,69 @@
 use chrono::prelude::*;
+use std::collections::VecDeque;
 use std::io::Write;
+use std::sync::Mutex;
 use std::thread;
 use std::time::Duration;
 use std::time::Instant;
 use env_logger;
 
+// log records kept for the Console widgets
+const MAX_RECORDS: usize = 1000;
+
+/// A log record as shown by the Console widget.
+#[derive(Debug, Clone)]
+pub struct LogLine {
+    pub seq: u64,
+    pub time: DateTime<Local>,
+    pub level: log::Level,
+    pub text: String,
+}
+
+static RECORDS: Mutex<(u64, VecDeque<LogLine>)> = Mutex::new((0, VecDeque::new()));
+
+/// Passes records to env_logger and keeps the last ones for the UI.
+struct TeeLogger {
+    inner: env_logger::Logger,
+}
+
+impl log::Log for TeeLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.inner.matches(record) {
+            return;
+        }
+        self.inner.log(record);
+        if let Ok(mut records) = RECORDS.lock() {
+            let (seq, lines) = &mut *records;
+            *seq += 1;
+            lines.push_back(LogLine {
+                seq: *seq,
+                time: Local::now(),
+                level: record.level(),
+                text: format!("{}", record.args()),
+            });
+            if lines.len() > MAX_RECORDS {
+                lines.pop_front();
+            }
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// The log records after sequence number `seq`, oldest first.
+pub fn log_lines_since(seq: u64) -> Vec<LogLine> {
+    RECORDS
+        .lock()
+        .map(|records| records.1.iter().filter(|l| l.seq > seq).cloned().collect())
+        .unwrap_or_default()
+}
+
 pub fn init_logger() {
     println!("init logger");
     let mut builder = env_logger::Builder::from_default_env();
@@ -23,6 +82,10 @@ pub fn init_logger() {
                 record.args()
             )
         })
-        .filter(None, log::LevelFilter::Info)
-        .init();
+        .filter(None, log::LevelFilter::Info);
+    let inner = builder.build();
+    log::set_max_level(inner.filter());
+    if log::set_boxed_logger(Box::new(TeeLogger { inner })).is_err() {
+        eprintln!("logger already set");
+    }
 }
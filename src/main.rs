@@ -4,7 +4,10 @@
 #![allow(unused_mut)]
 
 use app::wait_for;
-use config::file_xml::{get_widget_params, load_dashboard, load_xml_file};
+use config::file_xml::{
+    get_widget_params, load_alarm_rules, load_dashboard, load_notify_actions, load_palette,
+    load_xml_file, WidgetParams,
+};
 use fltk::valuator::Dial;
 use limero::{ActorTrait, SinkRef, SinkTrait, SourceTrait};
 use minidom::Element;
@@ -57,13 +60,17 @@ use tokio::time::{self, Duration};
 use tokio::{select, task};
 use tokio_stream::StreamExt;
 
+mod alarm;
 mod config;
 mod logger;
 mod pubsub;
 use pubsub::zenoh_pubsub::*;
+use config::file_change::{FileChange, FileChangeEvent};
 mod store;
 mod widget;
+use alarm::{AlarmRule, Severity};
 use logger::init_logger;
+use pubsub::router::TopicRouter;
 use pubsub::{mqtt_pubsub, redis_bridge, PubSubCmd, PubSubEvent};
 use store::sub_table::EntryList;
 use widget::sub_gauge::SubGauge;
@@ -71,12 +78,22 @@ use widget::sub_label::SubLabel;
 use widget::BrokerAlive;
 use widget::PubButton;
 use widget::PubSubWidget;
+use widget::pages::{handle_page_keys, Pages};
+use widget::theme::apply_theme;
+use widget::dispatcher::{Dispatcher, Reloader, UiMsg};
+use widget::edit::{attach_editor, Editor};
+use widget::notify::{attach_notifier, NotifyAction};
+use widget::registry::{Services, WidgetRegistry};
+use widget::scheduler::{RenderScheduler, DEFAULT_FPS};
 
 use widget::*;
 mod limero;
 
 use rand::random;
 
+const CONFIG_FILE: &str = "./config.xml";
+const UI_QUEUE_SIZE: usize = 1000;
+
 pub fn default_str(opt: Option<String>, default: &str) -> String {
     opt.unwrap_or(default.to_string())
 }
@@ -84,7 +101,6 @@ pub fn default_str(opt: Option<String>, default: &str) -> String {
 #[derive(Debug)]
 enum MyError<'a> {
     Io(std::io::Error),
-    Xml(minidom::Error),
     Yaml(serde_yaml::Error),
     Str(&'a str),
     String(String),
@@ -129,6 +145,81 @@ fn start_pubsub_mqtt(
     Ok(pubsub_cmd)
 }
 
+fn create_widgets(
+    widgets_params: &[WidgetParams],
+    widgets: &mut Vec<Box<dyn PubSubWidget>>,
+    registry: &WidgetRegistry,
+    services: &Services,
+    editor: &Rc<RefCell<Editor>>,
+) -> Result<(), String> {
+    for widget_params in widgets_params {
+        let parent = Group::try_current();
+        let first_child = parent.as_ref().map_or(0, |p| p.children());
+        widgets.push(registry.create(widget_params, services)?);
+        // everything the widget added to the current group moves as one
+        if let Some(parent) = parent {
+            let parts = (first_child..parent.children())
+                .filter_map(|i| parent.child(i))
+                .collect();
+            editor.borrow_mut().register(widget_params, parts);
+        }
+    }
+    Ok(())
+}
+
+fn load_alarms(dashboard_config: &Element) -> Result<Vec<AlarmRule>, String> {
+    AlarmRule::from_rules(&load_alarm_rules(dashboard_config))
+}
+
+fn load_notify(dashboard_config: &Element) -> Result<Vec<(Severity, NotifyAction)>, String> {
+    load_notify_actions(dashboard_config)
+        .iter()
+        .map(NotifyAction::from_attrs)
+        .collect()
+}
+
+/// Creates the widgets and pages of the dashboard in the current group,
+/// returns the widgets and the page rotation timer.
+fn build_dashboard(
+    widgets_params: Vec<WidgetParams>,
+    rect: Rect,
+    win: &mut window::Window,
+    registry: &WidgetRegistry,
+    services: &Services,
+    editor: &Rc<RefCell<Editor>>,
+    rotate: i32,
+) -> Result<(Vec<Box<dyn PubSubWidget>>, Option<app::TimeoutHandle>), String> {
+    let mut widgets = Vec::<Box<dyn PubSubWidget>>::new();
+    let (pages_params, widgets_params): (Vec<WidgetParams>, Vec<WidgetParams>) = widgets_params
+        .into_iter()
+        .partition(|p| p.name == "Tab" || p.name == "Page");
+    create_widgets(&widgets_params, &mut widgets, registry, services, editor)?;
+    if pages_params.is_empty() {
+        // drop the shortcuts of the pages of a previous load
+        win.handle(|_, _| false);
+        return Ok((widgets, None));
+    }
+    let pages = Pages::new(rect, &pages_params, |page_params| {
+        create_widgets(&page_params.children, &mut widgets, registry, services, editor)
+    })?;
+    handle_page_keys(win, &pages);
+    let rotate_timer = pages.auto_rotate(rotate);
+    Ok((widgets, rotate_timer))
+}
+
+/// The Dashboard attributes applied once at startup, a reload keeps the
+/// values it started with.
+fn needs_restart(started: &WidgetParams, loaded: &WidgetParams) -> bool {
+    started.theme != loaded.theme
+        || started.scheme != loaded.scheme
+        || started.bg != loaded.bg
+        || started.fg != loaded.fg
+        || started.width != loaded.width
+        || started.height != loaded.height
+        || started.fps != loaded.fps
+        || started.stats != loaded.stats
+}
+
 #[tokio::main(flavor = "multi_thread", worker_threads = 4)]
 async fn main() -> Result<(), MyError<'static>> {
     env::set_var("RUST_LOG", "info");
@@ -137,7 +228,7 @@ async fn main() -> Result<(), MyError<'static>> {
 
     let mut event_sink = limero::Sink::new(100);
 
-    let root_config = load_xml_file("./config.xml").map_err(MyError::Xml)?;
+    let root_config = load_xml_file(CONFIG_FILE).map_err(MyError::String)?;
 
     let pubsub_config = root_config
         .get_child("PubSub", "")
@@ -150,101 +241,160 @@ async fn main() -> Result<(), MyError<'static>> {
         .get_child("Dashboard", "")
         .ok_or(MyError::Str("Dashboard section not found"))?;
     let widgets_params = load_dashboard(&dashboard_config).map_err(MyError::String)?;
-    let mut context = Context::new();
+    let alarm_rules = load_alarms(&dashboard_config).map_err(MyError::String)?;
+    let notify_actions = load_notify(&dashboard_config).map_err(MyError::String)?;
     let window_params =
         get_widget_params(Rect::new(0, 0, 0, 0), &dashboard_config).map_err(MyError::String)?;
+    info!("Starting up fltk");
 
+    let mut _app = App::default().with_scheme(AppScheme::Oxy);
+    let mut context = apply_theme(&window_params, &load_palette(&dashboard_config));
     context.screen_width = window_params.width.unwrap_or(1024);
     context.screen_height = window_params.height.unwrap_or(768);
+    Context::set_current(context.clone());
     let window_rect = Rect::new(0, 0, context.screen_width, context.screen_height);
-    info!("Starting up fltk");
-
-    let mut _app = App::default().with_scheme(AppScheme::Oxy);
     let mut win = window::Window::default()
         .with_size(context.screen_width, context.screen_height)
         .with_label(&default_str(window_params.label, "FLTK dashboard").as_str());
     win.make_resizable(true);
+    win.set_color(context.background_color);
 
-    let mut widgets = Vec::<Box<dyn PubSubWidget>>::new();
+    let mut config_sink = limero::Sink::<WidgetParams>::new(10);
+    let editor = Editor::new(CONFIG_FILE, &context, config_sink.sink_ref());
+    let registry = WidgetRegistry::with_defaults();
+    let services = Services::new(pubsub_cmd.clone(), context.clone());
+    services.alarms.borrow_mut().set_rules(alarm_rules);
+    services.notifier.borrow_mut().set_actions(notify_actions);
+    // everything rebuilt on a reload lives in this group
+    let mut content = Group::new(window_rect.x, window_rect.y, window_rect.w, window_rect.h, None);
+    let (widgets, mut rotate_timer) = build_dashboard(
+        widgets_params,
+        window_rect,
+        &mut win,
+        &registry,
+        &services,
+        &editor,
+        window_params.rotate.unwrap_or(0),
+    )
+    .map_err(MyError::String)?;
+    content.end();
 
-    for widget_params in widgets_params {
-        let widget_type = widget_params.name.as_str();
-        info!("Loading widget {}", widget_type);
-        match widget_type {
-            "Gauge" => {
-                let mut widget = SubLabel::new(&widget_params);
-                widget.draw();
-
-                widgets.push(Box::new(widget));
+    attach_notifier(services.notifier.clone(), window_rect);
+    let alarms = services.alarms.clone();
+    let started = window_params.clone();
+    let started_palette = load_palette(&dashboard_config);
+    let reloader: Reloader = {
+        let editor = editor.clone();
+        let mut win = win.clone();
+        Box::new(move |dispatcher| {
+            // a broken config file leaves the running dashboard alone
+            let root_config = load_xml_file(CONFIG_FILE)?;
+            let dashboard_config = root_config
+                .get_child("Dashboard", "")
+                .ok_or("Dashboard section not found")?;
+            let widgets_params = load_dashboard(dashboard_config)?;
+            let alarm_rules = load_alarms(dashboard_config)?;
+            let notify_actions = load_notify(dashboard_config)?;
+            let window_params = get_widget_params(Rect::new(0, 0, 0, 0), dashboard_config)?;
+            if needs_restart(&started, &window_params)
+                || load_palette(dashboard_config) != started_palette
+            {
+                warn!("Theme, scheme, palette, colours, size, fps and stats of the Dashboard only change on a restart");
             }
-            "Label" => {
-                let mut widget = SubLabel::new(&widget_params);
-                widget.draw();
 
-                widgets.push(Box::new(widget));
-            }
-            "BrokerAlive" => {
-                let mut widget = BrokerAlive::new(&widget_params, pubsub_cmd.clone());
-                widget.draw();
-                widgets.push(Box::new(widget));
-            }
-            "Table" => {
-                let mut widget = SubLabel::new(&widget_params);
-                widget.draw();
-            }
-            "Progress" => {
-                let mut widget = SubLabel::new(&widget_params);
-                widget.draw();
-            }
-            "Plot" => {
-                let mut widget = SubLabel::new(&widget_params);
-                widget.draw();
-            }
-            "Button" => {
-                let mut widget = PubButton::new(&widget_params, pubsub_cmd.clone());
-                widget.draw();
-            }
-            _ => {
-                warn!("Unknown widget type {}", widget_type);
-            }
-        };
+            // the new widgets are built in a group of their own next to the
+            // running ones, which are only torn down once that succeeded
+            let registered = editor.borrow().item_count();
+            let mut fresh = Group::new(window_rect.x, window_rect.y, window_rect.w, window_rect.h, None);
+            fresh.end();
+            // before the editor overlay, which must stay the last child
+            win.insert(&fresh, win.find(&content));
+            fresh.begin();
+            let built = build_dashboard(
+                widgets_params,
+                window_rect,
+                &mut win,
+                &registry,
+                &services,
+                &editor,
+                window_params.rotate.unwrap_or(0),
+            );
+            fresh.end();
+            Group::set_current(None::<&Group>);
+            let (widgets, timer) = match built {
+                Ok(built) => built,
+                Err(e) => {
+                    editor.borrow_mut().truncate(registered);
+                    win.remove(&fresh);
+                    app::delete_widget(fresh);
+                    win.redraw();
+                    return Err(e);
+                }
+            };
+            let states = dispatcher.teardown();
+            rotate_timer.take().map(app::remove_timeout3);
+            rotate_timer = timer;
+            editor.borrow_mut().forget_first(registered);
+            let old = std::mem::replace(&mut content, fresh);
+            win.remove(&old);
+            app::delete_widget(old);
+            dispatcher.set_widgets(widgets, states);
+            services.alarms.borrow_mut().set_rules(alarm_rules);
+            services.notifier.borrow_mut().set_actions(notify_actions);
+            win.redraw();
+            Ok(())
+        })
+    };
+    let (ui_tx, ui_rx) = channel::<UiMsg>(UI_QUEUE_SIZE);
+    let mut scheduler = RenderScheduler::new(
+        Dispatcher::new(widgets)
+            .with_reloader(reloader)
+            .with_alarms(alarms),
+        ui_rx,
+        window_params.fps.unwrap_or(DEFAULT_FPS),
+    );
+    if window_params.stats.unwrap_or(false) {
+        scheduler = scheduler.with_stats(context.screen_width);
     }
+    attach_editor(editor, window_rect);
 
     win.end();
     win.show();
 
 
 
-    //   tokio::time::sleep(Duration::from_secs(100)).await;
+    let mut file_sink = limero::Sink::<FileChangeEvent>::new(1);
+    let mut file_change = FileChange::new(CONFIG_FILE.to_string());
+    file_change.add_listener(file_sink.sink_ref());
+    tokio::spawn(async move {
+        file_change.run().await;
+    });
 
-    let _jh = tokio::spawn(  async move   {
+    // widgets stay on the UI thread, the tokio side only queues messages
+    let _jh = tokio::spawn(async move {
         loop {
-        // info!("Waiting for event");
-        select! {
-            m = event_sink.next() => {
-                if m.is_some() {
-                    match m.unwrap() {
-                        PubSubEvent::Publish{topic, payload} => {
-                        for widget in widgets.iter_mut() {
-                            widget.update(&WidgetMsg::Pub { topic:topic.clone(),payload:payload.clone() });
-                        }
-                    },
-                    _ => {}
-                }
-            }},
-            t = time::sleep(Duration::from_millis(1000)) => {
-                for widget in widgets.iter_mut() {
-                    widget.update(&WidgetMsg::Tick );
-                }
+            let msg = select! {
+                m = event_sink.next() => match m {
+                    Some(event) => UiMsg::PubSub(event),
+                    None => continue,
+                },
+                c = config_sink.next() => match c {
+                    Some(cfg) => UiMsg::Config(cfg),
+                    None => continue,
+                },
+                f = file_sink.next() => match f {
+                    Some(_) => UiMsg::Reload,
+                    None => continue,
+                },
+            };
+            if ui_tx.send(msg).await.is_err() {
+                error!("UI thread gone");
+                break;
             }
-        };
-        awake();
-    };
+        }
     });
 
-
-    while _app.wait() {
-        win.redraw();
-    }
+    scheduler.start();
+    _app.run().map_err(MyError::Fltk)?;
     Ok(())
 }
@@ -1,5 +1,7 @@
 pub mod mqtt_pubsub;
 pub mod redis_bridge;
+pub mod router;
+pub mod topic_matcher;
 
 pub mod zenoh_pubsub;
 use std::convert::Infallible;
@@ -39,6 +41,104 @@ where
     _x.unwrap().writer().to_vec()
 }
 
+/// Encodes a JSON document as the equivalent CBOR payload.
+pub fn json_encode(text: &str) -> Result<Vec<u8>, String> {
+    let value: serde_json::Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let mut buffer = Vec::<u8>::new();
+    encode_json(&mut Encoder::new(&mut buffer), &value).map_err(|e| e.to_string())?;
+    Ok(buffer)
+}
+
+fn encode_json<W: encode::Write>(
+    encoder: &mut Encoder<W>,
+    value: &serde_json::Value,
+) -> Result<(), encode::Error<W::Error>> {
+    use serde_json::Value as Json;
+    match value {
+        Json::Null => {
+            encoder.null()?;
+        }
+        Json::Bool(b) => {
+            encoder.bool(*b)?;
+        }
+        Json::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                encoder.i64(i)?;
+            } else if let Some(u) = n.as_u64() {
+                encoder.u64(u)?;
+            } else {
+                encoder.f64(n.as_f64().unwrap_or(f64::NAN))?;
+            }
+        }
+        Json::String(s) => {
+            encoder.str(s)?;
+        }
+        Json::Array(items) => {
+            encoder.array(items.len() as u64)?;
+            for item in items {
+                encode_json(encoder, item)?;
+            }
+        }
+        Json::Object(fields) => {
+            encoder.map(fields.len() as u64)?;
+            for (key, item) in fields {
+                encoder.str(key)?;
+                encode_json(encoder, item)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Decodes a CBOR payload into a JSON value, for widgets that pick fields
+/// out of structured payloads. Byte strings become arrays of numbers.
+pub fn payload_to_json(payload: &Vec<u8>) -> Result<serde_json::Value, decode::Error> {
+    decode_json(&mut Decoder::new(payload))
+}
+
+fn decode_json(decoder: &mut Decoder) -> Result<serde_json::Value, decode::Error> {
+    use serde_json::Value as Json;
+    let value = match decoder.datatype()? {
+        Type::Bool => Json::Bool(decoder.bool()?),
+        Type::Null | Type::Undefined => {
+            decoder.skip()?;
+            Json::Null
+        }
+        Type::U8 | Type::U16 | Type::U32 | Type::U64 => Json::from(decoder.u64()?),
+        Type::I8 | Type::I16 | Type::I32 | Type::I64 => Json::from(decoder.i64()?),
+        Type::F16 => Json::from(decoder.f16()? as f64),
+        Type::F32 => Json::from(decoder.f32()? as f64),
+        Type::F64 => Json::from(decoder.f64()?),
+        Type::String => Json::from(decoder.str()?),
+        Type::Bytes => Json::from(decoder.bytes()?.to_vec()),
+        Type::Array => {
+            let len = decoder.array()?.unwrap_or(0);
+            let items = (0..len)
+                .map(|_| decode_json(decoder))
+                .collect::<Result<Vec<_>, _>>()?;
+            Json::Array(items)
+        }
+        Type::Map => {
+            let len = decoder.map()?.unwrap_or(0);
+            let mut fields = serde_json::Map::new();
+            for _ in 0..len {
+                let key = match decode_json(decoder)? {
+                    Json::String(key) => key,
+                    other => other.to_string(),
+                };
+                fields.insert(key, decode_json(decoder)?);
+            }
+            Json::Object(fields)
+        }
+        Type::Tag => {
+            decoder.tag()?;
+            decode_json(decoder)?
+        }
+        other => return Err(Error::type_mismatch(other)),
+    };
+    Ok(value)
+}
+
 pub fn payload_decode<'a,T>(v: &'a Vec<u8>) -> Result<T, decode::Error>
 where T : Decode<'a,()>
 {
@@ -57,22 +157,63 @@ pub fn payload_display(v: &Vec<u8>) -> String {
     }
 }
 
+/// The first CBOR item of the payload as a number, a bool counts as 0 or 1.
+/// An empty payload or anything else, such as text, is an error.
 pub fn payload_as_f64 (payload: &Vec<u8>) -> Result<f64, decode::Error> {
     let mut decoder = Decoder::new(payload);
     let v =  decoder.tokens().collect::<Result<Vec<Token>, _>>()?;
-    match v[0] {
-        Token::F16(f) => Ok(f as f64),
-        Token::F32(f) => Ok(f as f64),
-        Token::F64(f) => Ok(f),
-        Token::I16(i) => Ok(i as f64),
-        Token::I32(i) => Ok(i as f64),
-        Token::I64(i) => Ok(i as f64),
-        Token::U16(i) => Ok(i as f64),
-        Token::U32(i) => Ok(i as f64),
-        Token::U64(i) => Ok(i as f64),
-        Token::I8(i) => Ok(i as f64),
-        Token::U8(i) => Ok(i as f64),
-        Token::Bool(b) => Ok(if b { 1.0 } else { 0.0 }),
-        _ => Err(Error::type_mismatch(decoder.datatype().unwrap())),
+    match v.first() {
+        Some(Token::F16(f)) => Ok(*f as f64),
+        Some(Token::F32(f)) => Ok(*f as f64),
+        Some(Token::F64(f)) => Ok(*f),
+        Some(Token::I16(i)) => Ok(*i as f64),
+        Some(Token::I32(i)) => Ok(*i as f64),
+        Some(Token::I64(i)) => Ok(*i as f64),
+        Some(Token::U16(i)) => Ok(*i as f64),
+        Some(Token::U32(i)) => Ok(*i as f64),
+        Some(Token::U64(i)) => Ok(*i as f64),
+        Some(Token::I8(i)) => Ok(*i as f64),
+        Some(Token::U8(i)) => Ok(*i as f64),
+        Some(Token::Bool(b)) => Ok(if *b { 1.0 } else { 0.0 }),
+        Some(token) => Err(Error::message(format!("not a number : {}", token))),
+        None => Err(Error::end_of_input()),
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn as_f64_empty() {
+        assert!(payload_as_f64(&Vec::new()).is_err());
+    }
+
+    #[test]
+    fn as_f64_text() {
+        assert!(payload_as_f64(&payload_encode("12.5")).is_err());
+    }
+
+    #[test]
+    fn as_f64_bool() {
+        assert_eq!(payload_as_f64(&payload_encode(true)).unwrap(), 1.0);
+        assert_eq!(payload_as_f64(&payload_encode(false)).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn as_f64_int() {
+        assert_eq!(payload_as_f64(&payload_encode(42u8)).unwrap(), 42.0);
+        assert_eq!(payload_as_f64(&payload_encode(-1000i32)).unwrap(), -1000.0);
+        assert_eq!(payload_as_f64(&payload_encode(1u64 << 40)).unwrap(), (1u64 << 40) as f64);
+    }
+
+    #[test]
+    fn as_f64_float() {
+        assert_eq!(payload_as_f64(&payload_encode(1.5f32)).unwrap(), 1.5);
+        assert_eq!(payload_as_f64(&payload_encode(-0.25f64)).unwrap(), -0.25);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn as_f64_invalid_cbor() {
+        assert!(payload_as_f64(&vec![0xFF, 0x00]).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use log::{info, warn};
+
+use crate::pubsub::topic_matcher::TopicPattern;
+
+/// Index from subscriptions to the widgets that asked for them. Exact topics
+/// are a hash lookup, only wildcard subscriptions are tested one by one.
+#[derive(Default)]
+pub struct TopicRouter {
+    exact: HashMap<String, Vec<usize>>,
+    patterns: Vec<(TopicPattern, Vec<usize>)>,
+}
+
+impl TopicRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, subscription: &str, id: usize) {
+        match TopicPattern::compile(subscription) {
+            Ok(TopicPattern::Exact(topic)) => self.exact.entry(topic).or_default().push(id),
+            Ok(pattern) => {
+                match self
+                    .patterns
+                    .iter_mut()
+                    .find(|(p, _)| p.source() == pattern.source())
+                {
+                    Some((_, ids)) => ids.push(id),
+                    None => self.patterns.push((pattern, vec![id])),
+                }
+            }
+            Err(e) => warn!("Invalid subscription {} : {}", subscription, e),
+        }
+    }
+
+    /// Undoes one add of `subscription` for `id`.
+    pub fn remove(&mut self, subscription: &str, id: usize) {
+        let forget = |ids: &mut Vec<usize>| {
+            if let Some(pos) = ids.iter().position(|i| *i == id) {
+                ids.remove(pos);
+            }
+        };
+        match TopicPattern::compile(subscription) {
+            Ok(TopicPattern::Exact(topic)) => {
+                if let Some(ids) = self.exact.get_mut(&topic) {
+                    forget(ids);
+                    if ids.is_empty() {
+                        self.exact.remove(&topic);
+                    }
+                }
+            }
+            Ok(pattern) => {
+                for (_, ids) in self
+                    .patterns
+                    .iter_mut()
+                    .filter(|(p, _)| p.source() == pattern.source())
+                {
+                    forget(ids);
+                }
+                self.patterns.retain(|(_, ids)| !ids.is_empty());
+            }
+            Err(e) => warn!("Invalid subscription {} : {}", subscription, e),
+        }
+    }
+
+    /// The ids subscribed to `topic`, each id at most once, with the parts
+    /// of the topic matched by the wildcards of its subscription.
+    pub fn route(&self, topic: &str) -> Vec<(usize, Arc<Vec<String>>)> {
+        let mut routes: Vec<(usize, Arc<Vec<String>>)> = Vec::new();
+        if let Some(ids) = self.exact.get(topic) {
+            let none = Arc::new(Vec::new());
+            routes.extend(ids.iter().map(|id| (*id, none.clone())));
+        }
+        for (pattern, pattern_ids) in self.patterns.iter() {
+            if let Some(captures) = pattern.captures(topic) {
+                let captures = Arc::new(captures);
+                routes.extend(pattern_ids.iter().map(|id| (*id, captures.clone())));
+            }
+        }
+        if !self.patterns.is_empty() {
+            routes.sort_by_key(|(id, _)| *id);
+            routes.dedup_by_key(|(id, _)| *id);
+        }
+        routes
+    }
+
+    pub fn log_stats(&self) {
+        info!(
+            "Router : {} exact topics, {} wildcard subscriptions",
+            self.exact.len(),
+            self.patterns.len()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(router: &TopicRouter, topic: &str) -> Vec<usize> {
+        router.route(topic).into_iter().map(|(id, _)| id).collect()
+    }
+
+    #[test]
+    fn exact_subscription() {
+        let mut router = TopicRouter::new();
+        router.add("src/esp1/temp", 1);
+        assert_eq!(ids(&router, "src/esp1/temp"), vec![1]);
+        assert!(ids(&router, "src/esp1/hum").is_empty());
+    }
+
+    #[test]
+    fn fan_out_to_all_subscribers() {
+        let mut router = TopicRouter::new();
+        router.add("src/esp1/temp", 3);
+        router.add("src/esp1/temp", 1);
+        router.add("src/+/temp", 2);
+        router.add("src/#", 4);
+        assert_eq!(ids(&router, "src/esp1/temp"), vec![1, 2, 3, 4]);
+        assert_eq!(ids(&router, "src/esp2/temp"), vec![2, 4]);
+        assert_eq!(ids(&router, "dst/esp1/temp"), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn id_routed_once() {
+        let mut router = TopicRouter::new();
+        router.add("src/esp1/temp", 1);
+        router.add("src/+/temp", 1);
+        router.add("src/#", 1);
+        assert_eq!(ids(&router, "src/esp1/temp"), vec![1]);
+    }
+
+    #[test]
+    fn wildcard_captures() {
+        let mut router = TopicRouter::new();
+        router.add("src/+/temp", 1);
+        let routes = router.route("src/esp1/temp");
+        assert_eq!(routes.len(), 1);
+        assert_eq!(*routes[0].1, vec!["esp1".to_string()]);
+    }
+
+    #[test]
+    fn unsubscribe() {
+        let mut router = TopicRouter::new();
+        router.add("src/esp1/temp", 1);
+        router.add("src/esp1/temp", 2);
+        router.add("src/+/temp", 3);
+        router.remove("src/esp1/temp", 1);
+        assert_eq!(ids(&router, "src/esp1/temp"), vec![2, 3]);
+        router.remove("src/+/temp", 3);
+        router.remove("src/esp1/temp", 2);
+        assert!(ids(&router, "src/esp1/temp").is_empty());
+        assert!(router.exact.is_empty());
+        assert!(router.patterns.is_empty());
+    }
+
+    #[test]
+    fn invalid_subscription_ignored() {
+        let mut router = TopicRouter::new();
+        router.add("src/#/temp", 1);
+        assert!(ids(&router, "src/x/temp").is_empty());
+    }
+}
@@ -0,0 +1,217 @@
+use regex::Regex;
+
+/// A compiled subscription. The syntax is derived from the pattern itself :
+/// `+` and `#` chunks are MQTT wildcards, `*` and `**` chunks are Zenoh key
+/// expressions, only a `regex:` prefix makes it a regex and anything else is
+/// matched literally, so `$SYS/broker/#` or `src/esp.1/temp` mean what they say.
+#[derive(Debug, Clone)]
+pub enum TopicPattern {
+    Exact(String),
+    Mqtt(Vec<String>),
+    Zenoh(Vec<String>),
+    Regex(Regex),
+}
+
+impl TopicPattern {
+    pub fn compile(pattern: &str) -> Result<TopicPattern, String> {
+        if let Some(re) = pattern.strip_prefix("regex:") {
+            return Self::regex(re);
+        }
+        let chunks: Vec<String> = pattern.split('/').map(String::from).collect();
+        if chunks.iter().any(|c| c == "+" || c == "#") {
+            if chunks.iter().position(|c| c == "#").is_some_and(|i| i != chunks.len() - 1) {
+                return Err(format!("'#' must be the last level in {}", pattern));
+            }
+            Ok(TopicPattern::Mqtt(chunks))
+        } else if chunks.iter().any(|c| c == "*" || c == "**") {
+            Ok(TopicPattern::Zenoh(chunks))
+        } else {
+            Ok(TopicPattern::Exact(pattern.to_string()))
+        }
+    }
+
+    fn regex(pattern: &str) -> Result<TopicPattern, String> {
+        // anchored, a subscription must match the whole topic
+        Regex::new(&format!("^(?:{})$", pattern))
+            .map(TopicPattern::Regex)
+            .map_err(|e| e.to_string())
+    }
+
+    /// The pattern as written, identical subscriptions share one entry.
+    pub fn source(&self) -> String {
+        match self {
+            TopicPattern::Exact(t) => t.clone(),
+            TopicPattern::Mqtt(chunks) | TopicPattern::Zenoh(chunks) => chunks.join("/"),
+            TopicPattern::Regex(re) => re.as_str().to_string(),
+        }
+    }
+
+    pub fn matches(&self, topic: &str) -> bool {
+        match self {
+            TopicPattern::Exact(t) => t == topic,
+            TopicPattern::Regex(re) => re.is_match(topic),
+            _ => self.captures(topic).is_some(),
+        }
+    }
+
+    /// The parts of `topic` matched by the wildcards, in order : one entry per
+    /// `+` or `*` level, the remaining levels joined by '/' for `#` or `**`,
+    /// and the capture groups of a regex. None when the topic doesn't match.
+    pub fn captures(&self, topic: &str) -> Option<Vec<String>> {
+        match self {
+            TopicPattern::Exact(t) => (t == topic).then(Vec::new),
+            TopicPattern::Mqtt(chunks) => {
+                let levels: Vec<&str> = topic.split('/').collect();
+                mqtt_captures(chunks, &levels)
+            }
+            TopicPattern::Zenoh(chunks) => {
+                let levels: Vec<&str> = topic.split('/').collect();
+                let mut captures = Vec::new();
+                zenoh_captures(chunks, &levels, &mut captures).then_some(captures)
+            }
+            TopicPattern::Regex(re) => re.captures(topic).map(|caps| {
+                caps.iter()
+                    .skip(1)
+                    .map(|m| m.map_or(String::new(), |m| m.as_str().to_string()))
+                    .collect()
+            }),
+        }
+    }
+}
+
+fn mqtt_captures(chunks: &[String], levels: &[&str]) -> Option<Vec<String>> {
+    let mut captures = Vec::new();
+    for (idx, chunk) in chunks.iter().enumerate() {
+        match chunk.as_str() {
+            // also matches the parent level itself
+            "#" => {
+                captures.push(levels.get(idx..).unwrap_or(&[]).join("/"));
+                return Some(captures);
+            }
+            "+" if idx < levels.len() => captures.push(levels[idx].to_string()),
+            c if idx < levels.len() && c == levels[idx] => {}
+            _ => return None,
+        }
+    }
+    (chunks.len() == levels.len()).then_some(captures)
+}
+
+fn zenoh_captures(chunks: &[String], levels: &[&str], captures: &mut Vec<String>) -> bool {
+    match chunks.split_first() {
+        None => levels.is_empty(),
+        Some((chunk, rest)) if chunk == "**" => {
+            for skip in 0..=levels.len() {
+                captures.push(levels[..skip].join("/"));
+                if zenoh_captures(rest, &levels[skip..], captures) {
+                    return true;
+                }
+                captures.pop();
+            }
+            false
+        }
+        Some((chunk, rest)) => match levels.split_first() {
+            Some((level, levels)) if chunk == "*" => {
+                captures.push(level.to_string());
+                if zenoh_captures(rest, levels, captures) {
+                    return true;
+                }
+                captures.pop();
+                false
+            }
+            Some((level, levels)) if chunk == level => zenoh_captures(rest, levels, captures),
+            _ => false,
+        },
+    }
+}
+
+/// Replaces $1, $2, .. in `text` by the matching wildcard captures.
+pub fn expand_captures(text: &str, captures: &[String]) -> String {
+    let mut text = text.to_string();
+    // highest index first so $1 doesn't eat the start of $10
+    for (idx, capture) in captures.iter().enumerate().rev() {
+        text = text.replace(&format!("${}", idx + 1), capture);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn captures(pattern: &str, topic: &str) -> Option<Vec<String>> {
+        TopicPattern::compile(pattern).unwrap().captures(topic)
+    }
+
+    fn strings(v: &[&str]) -> Option<Vec<String>> {
+        Some(v.iter().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn exact() {
+        assert!(matches!(TopicPattern::compile("src/esp1/temp"), Ok(TopicPattern::Exact(_))));
+        assert_eq!(captures("src/esp1/temp", "src/esp1/temp"), strings(&[]));
+        assert_eq!(captures("src/esp1/temp", "src/esp1/temp/x"), None);
+    }
+
+    #[test]
+    fn mqtt_plus() {
+        assert_eq!(captures("src/+/temp", "src/esp1/temp"), strings(&["esp1"]));
+        assert_eq!(captures("src/+/+", "src/esp1/temp"), strings(&["esp1", "temp"]));
+        assert_eq!(captures("src/+/temp", "src/esp1/x/temp"), None);
+        assert_eq!(captures("src/+/temp", "src/temp"), None);
+    }
+
+    #[test]
+    fn mqtt_hash() {
+        assert_eq!(captures("src/#", "src/esp1/sys/latency"), strings(&["esp1/sys/latency"]));
+        assert_eq!(captures("src/#", "src"), strings(&[""]));
+        assert_eq!(captures("src/#", "dst/esp1"), None);
+        assert!(TopicPattern::compile("src/#/temp").is_err());
+    }
+
+    #[test]
+    fn zenoh_star() {
+        assert_eq!(captures("src/*/temp", "src/esp1/temp"), strings(&["esp1"]));
+        assert_eq!(captures("src/*/temp", "src/esp1/x/temp"), None);
+    }
+
+    #[test]
+    fn zenoh_double_star() {
+        assert_eq!(captures("src/**/temp", "src/a/b/temp"), strings(&["a/b"]));
+        assert_eq!(captures("src/**/temp", "src/temp"), strings(&[""]));
+        assert_eq!(captures("src/**", "src/a/b"), strings(&["a/b"]));
+        assert_eq!(captures("src/**/temp", "src/a/hum"), None);
+    }
+
+    #[test]
+    fn regex_only_with_prefix() {
+        assert_eq!(captures("regex:src/(esp\\d)/temp", "src/esp1/temp"), strings(&["esp1"]));
+        assert_eq!(captures("regex:src/.*", "src/esp1/temp"), strings(&[]));
+        // anchored on both ends
+        assert_eq!(captures("regex:esp1", "src/esp1/temp"), None);
+        assert!(TopicPattern::compile("regex:(").is_err());
+    }
+
+    #[test]
+    fn regex_characters_are_literal() {
+        assert_eq!(captures("src/esp.1/temp", "src/esp.1/temp"), strings(&[]));
+        assert_eq!(captures("src/esp.1/temp", "src/espx1/temp"), None);
+        assert_eq!(captures(".*", "src/esp1/temp"), None);
+    }
+
+    #[test]
+    fn sys_topics() {
+        assert_eq!(
+            captures("$SYS/broker/#", "$SYS/broker/clients/connected"),
+            strings(&["clients/connected"])
+        );
+        assert_eq!(captures("$SYS/broker/uptime", "$SYS/broker/uptime"), strings(&[]));
+        assert_eq!(captures("$SYS/+/uptime", "$SYS/broker/uptime"), strings(&["broker"]));
+    }
+
+    #[test]
+    fn expand() {
+        let caps: Vec<String> = (1..=10).map(|i| format!("c{}", i)).collect();
+        assert_eq!(expand_captures("$1 $10 $SYS", &caps), "c1 c10 $SYS");
+    }
+}
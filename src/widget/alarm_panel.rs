@@ -0,0 +1,232 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use fltk::browser::SelectBrowser;
+use fltk::draw::Rect;
+use fltk::enums::Color;
+use fltk::{app, prelude::*};
+use log::warn;
+
+use crate::alarm::{Alarm, AlarmKey, AlarmManager, Severity};
+use crate::config::file_xml::WidgetParams;
+use crate::limero::{SinkRef, SinkTrait};
+use crate::pubsub::{json_encode, PubSubCmd};
+use crate::widget::theme::apply_style;
+use crate::widget::Context;
+
+use super::{PubSubWidget, WidgetMsg};
+
+const FLASH_PERIOD: Duration = Duration::from_millis(500);
+const DEFAULT_HISTORY: usize = 10;
+
+/// A line of the list, flashing lines alternate between line and inverted.
+struct Row {
+    key: Option<AlarmKey>,
+    line: String,
+    inverted: Option<String>,
+}
+
+/// The alarms of the alarm manager : unacknowledged ones first and flashing,
+/// then the acknowledged ones still active, then the last history finished
+/// ones. A click on an unacknowledged alarm acknowledges it and publishes
+/// the acknowledge on dst.
+pub struct AlarmPanel {
+    cfg: WidgetParams,
+    ctx: Context,
+    browser: Option<SelectBrowser>,
+    alarms: Rc<RefCell<AlarmManager>>,
+    pubsub_cmd: SinkRef<PubSubCmd>,
+    // the alarm on each line, for the browser callback
+    keys: Rc<RefCell<Vec<Option<AlarmKey>>>>,
+    rows: Vec<Row>,
+    generation: Option<u64>,
+    flash_on: bool,
+}
+
+impl AlarmPanel {
+    pub fn new(cfg: &WidgetParams, pubsub_cmd: SinkRef<PubSubCmd>, alarms: Rc<RefCell<AlarmManager>>) -> Self {
+        Self {
+            cfg: cfg.clone(),
+            ctx: Context::current(),
+            browser: None,
+            alarms,
+            pubsub_cmd,
+            keys: Rc::new(RefCell::new(Vec::new())),
+            rows: Vec::new(),
+            generation: None,
+            flash_on: false,
+        }
+    }
+
+    fn severity_color(&self, severity: Severity) -> Color {
+        match severity {
+            Severity::Critical => self.ctx.color("alarm", Color::from_u32(0xFF0000)),
+            Severity::Warning => self.ctx.color("warning", Color::from_u32(0xFFB000)),
+            Severity::Info => self.ctx.fg(&self.cfg),
+        }
+    }
+
+    fn row(&self, alarm: &Alarm, color: Color, flashing: bool) -> Row {
+        let mut text = format!(
+            "{} {:8} {} ({})",
+            time(&alarm.raised),
+            alarm.severity.to_string(),
+            alarm.message,
+            alarm.value
+        );
+        if let Some(t) = alarm.acknowledged.as_ref() {
+            text.push_str(&format!(" ack {}", time(t)));
+        }
+        if let Some(t) = alarm.cleared.as_ref() {
+            text.push_str(&format!(" cleared {}", time(t)));
+        }
+        let bg = self.ctx.bg(&self.cfg);
+        Row {
+            key: flashing.then(|| alarm.key()),
+            line: format!("@C{}@.{}", color.bits(), text),
+            inverted: flashing.then(|| format!("@B{}@C{}@.{}", color.bits(), bg.bits(), text)),
+        }
+    }
+
+    fn refresh(&mut self) {
+        let rows = {
+            let manager = self.alarms.borrow();
+            let mut pending: Vec<&Alarm> = manager.alarms().iter().filter(|a| a.acknowledged.is_none()).collect();
+            let mut acknowledged: Vec<&Alarm> = manager.alarms().iter().filter(|a| a.acknowledged.is_some()).collect();
+            // the most severe and most recent on top
+            for list in [&mut pending, &mut acknowledged] {
+                list.sort_by(|a, b| b.severity.cmp(&a.severity).then(b.raised.cmp(&a.raised)));
+            }
+            let stale = self.ctx.color("stale", Color::from_u32(0x808080));
+            let history = self.cfg.history.unwrap_or(DEFAULT_HISTORY);
+            pending
+                .iter()
+                .map(|a| self.row(a, self.severity_color(a.severity), true))
+                .chain(acknowledged.iter().map(|a| self.row(a, self.severity_color(a.severity), false)))
+                .chain(manager.history().iter().rev().take(history).map(|a| self.row(a, stale, false)))
+                .collect::<Vec<Row>>()
+        };
+        self.generation = Some(self.alarms.borrow().generation());
+        *self.keys.borrow_mut() = rows.iter().map(|r| r.key.clone()).collect();
+        if let Some(browser) = self.browser.as_mut() {
+            let position = browser.position();
+            browser.clear();
+            for row in rows.iter() {
+                browser.add(&row.line);
+            }
+            browser.set_position(position);
+        }
+        self.rows = rows;
+    }
+
+    fn flash(&mut self) {
+        self.flash_on = !self.flash_on;
+        let Some(browser) = self.browser.as_mut() else {
+            return;
+        };
+        for (idx, row) in self.rows.iter().enumerate() {
+            if let Some(inverted) = row.inverted.as_ref() {
+                let text = if self.flash_on { inverted } else { &row.line };
+                browser.set_text(idx as i32 + 1, text);
+            }
+        }
+    }
+}
+
+fn time(t: &DateTime<Local>) -> String {
+    t.format("%H:%M:%S").to_string()
+}
+
+fn ack_payload(alarm: &Alarm) -> Result<Vec<u8>, String> {
+    let ack = serde_json::json!({
+        "rule": alarm.rule,
+        "topic": alarm.topic,
+        "severity": alarm.severity.to_string(),
+        "raised": alarm.raised.to_rfc3339(),
+        "acknowledged": alarm.acknowledged.map(|t| t.to_rfc3339()),
+    });
+    json_encode(&ack.to_string())
+}
+
+impl PubSubWidget for AlarmPanel {
+    fn draw(&mut self) {
+        let r = self.cfg.rect;
+        let mut browser = SelectBrowser::new(r.x, r.y, r.w, r.h, None);
+        apply_style(&mut browser, &self.cfg, &self.ctx);
+        browser.set_text_size(self.cfg.text_size.unwrap_or(14));
+        browser.set_callback({
+            let alarms = self.alarms.clone();
+            let keys = self.keys.clone();
+            let pubsub_cmd = self.pubsub_cmd.clone();
+            let dst_topic = self.cfg.dst_topic.clone();
+            move |b| {
+                let line = b.value();
+                // the line is only a click target, not a selection to keep
+                b.deselect_all();
+                if line < 1 {
+                    return;
+                }
+                let key = keys.borrow().get(line as usize - 1).cloned().flatten();
+                let Some(alarm) = key.and_then(|k| alarms.borrow_mut().acknowledge(&k)) else {
+                    return;
+                };
+                if let Some(topic) = dst_topic.as_ref() {
+                    match ack_payload(&alarm) {
+                        Ok(payload) => pubsub_cmd.push(PubSubCmd::Publish {
+                            topic: topic.clone(),
+                            payload,
+                        }),
+                        Err(e) => warn!("Alarm acknowledge not published : {}", e),
+                    }
+                }
+            }
+        });
+        self.browser = Some(browser);
+        self.refresh();
+    }
+
+    fn get_config(&self) -> WidgetParams {
+        self.cfg.clone()
+    }
+
+    fn set_config(&mut self, cfg: WidgetParams) {
+        self.cfg = cfg;
+        if let Some(browser) = self.browser.as_mut() {
+            apply_style(browser, &self.cfg, &self.ctx);
+        }
+        self.refresh();
+    }
+
+    fn redraw(&mut self) {
+        self.browser.as_mut().map(|b| b.redraw());
+    }
+
+    fn subscriptions(&self) -> Vec<String> {
+        // the alarm manager sees every message
+        Vec::new()
+    }
+
+    fn tick_period(&self) -> Option<Duration> {
+        Some(FLASH_PERIOD)
+    }
+
+    fn resize(&mut self, rect: Rect) {
+        self.cfg.rect = rect;
+        self.browser.as_mut().map(|b| b.resize(rect.x, rect.y, rect.w, rect.h));
+    }
+
+    fn teardown(&mut self) {
+        self.browser.take().map(app::delete_widget);
+    }
+
+    fn update(&mut self, event: &WidgetMsg) {
+        if let WidgetMsg::Tick = event {
+            if self.generation != Some(self.alarms.borrow().generation()) {
+                self.refresh();
+            }
+            self.flash();
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use fltk::draw::Rect;
+use fltk::enums::{Align, Color, FrameType};
+use fltk::frame::Frame;
+use fltk::{app, draw, prelude::*};
+
+use crate::config::file_xml::WidgetParams;
+use crate::pubsub::payload_as_f64;
+use crate::pubsub::topic_matcher::{expand_captures, TopicPattern};
+use crate::widget::meter::{draw_label, range};
+use crate::widget::theme::apply_style;
+use crate::widget::Context;
+
+use super::{PubSubWidget, WidgetMsg};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(3000);
+const MAX_ROW_HEIGHT: i32 = 30;
+
+#[derive(Debug)]
+struct Bar {
+    label: String,
+    value: f64,
+    last_update: Instant,
+    amber: Option<f64>,
+    red: Option<f64>,
+}
+
+/// The amber and red levels of a <Bar> child, for the topics matching its src.
+#[derive(Debug)]
+struct Levels {
+    pattern: TopicPattern,
+    amber: Option<f64>,
+    red: Option<f64>,
+}
+
+/// One horizontal bar per topic matching src, a comma separated list of
+/// topics or wildcards such as "src/+/battery". A bar is labeled by prefix
+/// expanded with the wildcard captures, by default the captures themselves.
+/// All bars share min..max; amber and red color each bar on its own, and
+/// when red is below amber low values are the bad ones, as for batteries.
+/// A <Bar src=.. amber=.. red=..> child sets other levels for the topics
+/// matching its src, the first matching child wins.
+/// sort="value" or "-value" orders by value, otherwise bars sort by label.
+/// A bar without update for timeout msec turns to the stale color.
+pub struct BarChart {
+    cfg: WidgetParams,
+    ctx: Context,
+    frame: Option<Frame>,
+    bars: Rc<RefCell<BTreeMap<String, Bar>>>,
+    levels: Vec<Levels>,
+}
+
+impl BarChart {
+    pub fn new(cfg: &WidgetParams) -> Self {
+        let mut chart = Self {
+            cfg: cfg.clone(),
+            ctx: Context::current(),
+            frame: None,
+            bars: Rc::new(RefCell::new(BTreeMap::new())),
+            levels: Vec::new(),
+        };
+        chart.configure();
+        chart
+    }
+
+    fn configure(&mut self) {
+        self.levels = self
+            .cfg
+            .children
+            .iter()
+            .filter(|c| c.name == "Bar")
+            .filter_map(|bar| {
+                let src = bar.src_topic.as_deref()?;
+                match TopicPattern::compile(src) {
+                    Ok(pattern) => Some(Levels {
+                        pattern,
+                        amber: bar.amber,
+                        red: bar.red,
+                    }),
+                    Err(e) => {
+                        warn!("Invalid Bar src {} : {}", src, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+    }
+
+    fn set_draw(&mut self) {
+        let Some(frame) = self.frame.as_mut() else {
+            return;
+        };
+        let cfg = self.cfg.clone();
+        let ctx = self.ctx.clone();
+        let bars = self.bars.clone();
+        frame.draw(move |w| {
+            let bars = bars.borrow();
+            let (x, y, width, h) = draw_label(&cfg, &ctx, (w.x() + 2, w.y() + 2, w.w() - 4, w.h() - 4));
+            if bars.is_empty() {
+                return;
+            }
+            let (min, max) = range(&cfg);
+            let timeout = cfg
+                .timeout
+                .map_or(DEFAULT_TIMEOUT, |ms| Duration::from_millis(ms as u64));
+            let mut rows: Vec<&Bar> = bars.values().collect();
+            match cfg.sort.as_deref() {
+                Some("value") => rows.sort_by(|a, b| a.value.total_cmp(&b.value)),
+                Some("-value") => rows.sort_by(|a, b| b.value.total_cmp(&a.value)),
+                _ => rows.sort_by(|a, b| a.label.cmp(&b.label)),
+            }
+            let row_h = (h / rows.len() as i32).min(MAX_ROW_HEIGHT);
+            let label_w = width / 3;
+            let value_w = 60.min(width / 5);
+            let bar_w = width - label_w - value_w;
+            let fg = ctx.fg(&cfg);
+            draw::set_font(draw::font(), cfg.text_size.unwrap_or(12).min(row_h));
+            for (idx, bar) in rows.iter().enumerate() {
+                let ry = y + idx as i32 * row_h;
+                let fraction = ((bar.value - min) / (max - min)).clamp(0.0, 1.0);
+                let color = if bar.last_update.elapsed() > timeout {
+                    ctx.color("stale", Color::from_u32(0x808080))
+                } else {
+                    level_color(&cfg, &ctx, bar)
+                };
+                draw::set_draw_color(fg);
+                draw::draw_text2(&bar.label, x, ry, label_w - 4, row_h, Align::Right);
+                draw::draw_rect_fill(x + label_w, ry + 2, (bar_w as f64 * fraction) as i32, row_h - 4, color);
+                draw::set_draw_color(fg);
+                draw::draw_rect(x + label_w, ry + 2, bar_w, row_h - 4);
+                draw::draw_text2(
+                    &format!("{:.1}{}", bar.value, cfg.unit.as_deref().unwrap_or("")),
+                    x + label_w + bar_w,
+                    ry,
+                    value_w,
+                    row_h,
+                    Align::Right,
+                );
+            }
+        });
+    }
+}
+
+/// The color of a bar against its own amber and red levels, else those of the chart.
+fn level_color(cfg: &WidgetParams, ctx: &Context, bar: &Bar) -> Color {
+    let (min, max) = range(cfg);
+    let value = bar.value;
+    let amber = bar.amber.or(cfg.amber).unwrap_or(min + 0.7 * (max - min));
+    let red = bar.red.or(cfg.red).unwrap_or(min + 0.9 * (max - min));
+    // with red below amber, the low values are the bad ones
+    let beyond = |level: f64| if red < amber { value < level } else { value > level };
+    if beyond(red) {
+        ctx.color("alarm", Color::from_u32(0xFF0000))
+    } else if beyond(amber) {
+        ctx.color("warning", Color::from_u32(0xFFB000))
+    } else {
+        ctx.color("ok", Color::from_u32(0x00C000))
+    }
+}
+
+impl PubSubWidget for BarChart {
+    fn draw(&mut self) {
+        let r = self.cfg.rect;
+        let mut frame = Frame::new(r.x, r.y, r.w, r.h, None);
+        frame.set_frame(FrameType::FlatBox);
+        apply_style(&mut frame, &self.cfg, &self.ctx);
+        self.frame = Some(frame);
+        self.set_draw();
+    }
+
+    fn get_config(&self) -> WidgetParams {
+        self.cfg.clone()
+    }
+
+    fn set_config(&mut self, cfg: WidgetParams) {
+        self.cfg = cfg;
+        // the topics may have changed
+        self.bars.borrow_mut().clear();
+        self.configure();
+        if let Some(frame) = self.frame.as_mut() {
+            apply_style(frame, &self.cfg, &self.ctx);
+        }
+        self.set_draw();
+    }
+
+    fn redraw(&mut self) {
+        self.frame.as_mut().map(|f| f.redraw());
+    }
+
+    fn subscriptions(&self) -> Vec<String> {
+        let mut topics: Vec<String> = self
+            .cfg
+            .src_topic
+            .iter()
+            .chain(self.cfg.children.iter().filter_map(|c| c.src_topic.as_ref()))
+            .flat_map(|src| src.split(','))
+            .map(|topic| topic.trim().to_string())
+            .filter(|topic| !topic.is_empty())
+            .collect();
+        topics.sort();
+        topics.dedup();
+        topics
+    }
+
+    fn resize(&mut self, rect: Rect) {
+        self.cfg.rect = rect;
+        self.frame.as_mut().map(|f| f.resize(rect.x, rect.y, rect.w, rect.h));
+    }
+
+    fn teardown(&mut self) {
+        self.frame.take().map(app::delete_widget);
+    }
+
+    fn update(&mut self, event: &WidgetMsg) {
+        if let WidgetMsg::Pub { topic, payload, captures } = event {
+            let Ok(value) = payload_as_f64(payload) else {
+                return;
+            };
+            let label = match (self.cfg.prefix.as_ref(), captures.is_empty()) {
+                (Some(prefix), _) => expand_captures(prefix, captures),
+                (None, false) => captures.join("/"),
+                (None, true) => topic.to_string(),
+            };
+            let levels = self.levels.iter().find(|l| l.pattern.matches(topic));
+            self.bars.borrow_mut().insert(
+                topic.to_string(),
+                Bar {
+                    label,
+                    value,
+                    last_update: Instant::now(),
+                    amber: levels.and_then(|l| l.amber),
+                    red: levels.and_then(|l| l.red),
+                },
+            );
+        }
+    }
+}
@@ -10,14 +10,16 @@ use serde_yaml::Value;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
-use std::time::Instant;
-use std::time::SystemTime;
+use std::time::{Duration, Instant};
 
 use crate::config::file_xml::WidgetParams;
 use crate::limero::{SinkRef, SinkTrait};
 use crate::pubsub::{payload_as_f64, payload_decode, payload_encode, PubSubCmd, PubSubEvent};
 use crate::widget::hms;
+use crate::widget::notify::Notifier;
 use crate::widget::Context;
+use crate::widget::theme::apply_style;
+use crate::widget::timers::DEFAULT_TICK;
 use crate::WidgetMsg;
 use tokio::sync::mpsc;
 
@@ -29,28 +31,44 @@ use super::PubSubWidget;
 #[derive(Clone)]
 pub struct BrokerAlive {
     value: f64,
-    last_update: SystemTime,
+    // the last ping back, or the first ping sent until one is back
+    last_update: Option<Instant>,
     eval_expr: Option<Node>,
     cfg: WidgetParams,
     ctx: Context,
     frame: Option<fltk::frame::Frame>,
     topic: String,
     sinkref_cmd : SinkRef<PubSubCmd>,
+    alive: bool,
+    notifier: Rc<RefCell<Notifier>>,
 }
 
 impl BrokerAlive {
-    pub fn new(cfg: &WidgetParams,sinkref_cmd : SinkRef<PubSubCmd>) -> Self {
+    pub fn new(cfg: &WidgetParams,sinkref_cmd : SinkRef<PubSubCmd>, notifier: Rc<RefCell<Notifier>>) -> Self {
         // get random topic
         let topic = format!("dst/broker/alive/{}", random::<u32>());
         Self {
             value: 0.0,
-            last_update: std::time::UNIX_EPOCH,
+            last_update: None,
             eval_expr: None,
             cfg: cfg.clone(),
-            ctx: Context::new(),
+            ctx: Context::current(),
             frame: None,
             topic,
             sinkref_cmd,
+            alive: true,
+            notifier,
+        }
+    }
+
+    fn show_dead(&mut self) {
+        // once, the notifier may be blinking the frame
+        if self.alive {
+            self.alive = false;
+            let alarm = self.ctx.color("alarm", Color::from_u32(0xFF0000));
+            self.frame.as_mut().map(|f| f.set_color(alarm));
+            let text = format!("{} broker not responding", self.cfg.label.as_deref().unwrap_or("Broker"));
+            self.notifier.borrow_mut().notify_widget(&self.cfg, self.frame.as_ref(), &text);
         }
     }
 }
@@ -65,67 +83,78 @@ impl PubSubWidget for BrokerAlive {
             None,
         );
         frame.set_frame(FrameType::BorderBox);
-        frame.set_color(Color::from_u32(0xFFFFFF));
         frame.set_label_font(Font::HelveticaBold);
         frame.set_align(Align::Center);
-        let label = self.cfg.label.as_ref().unwrap().clone();
+        apply_style(&mut frame, &self.cfg, &self.ctx);
         self.cfg.label.as_ref().map(|s| frame.set_label(s.as_str()));
 
-        let origins = (self.cfg.rect.x, self.cfg.rect.y);
-        frame.handle({
-            let mut x = 0;
-            let mut y = 0;
-            let mut new_x = 0;
-            let mut new_y = 0;
-            let mut origins = origins;
-            move |w, ev| match ev {
-                enums::Event::Push => {
-                    if app::event_mouse_button() == app::MouseButton::Right {
-                        (x, y) = app::event_coords();
-                        true
-                    } else {
-                        false
-                    }
-                }
-                enums::Event::Drag => {
-                    if app::event_mouse_button() == app::MouseButton::Right {
-                        let (x1, y1) = app::event_coords();
-                        (new_x, new_y) = (origins.0 + x1 - x, origins.1 + y1 - y);
-                        true
-                    } else {
-                        false
-                    }
-                }
-                enums::Event::Released => {
-                    if app::event_mouse_button() == app::MouseButton::Right {
-                        origins = (new_x, new_y);
-                        true
-                    } else {
-                        false
-                    }
-                }
-                _ev => false,
-            }
-        });
         self.frame = Some(frame);
     }
 
+    fn get_config(&self) -> WidgetParams {
+        self.cfg.clone()
+    }
+
+    fn set_config(&mut self, cfg: WidgetParams) {
+        self.cfg = cfg;
+        if let Some(frame) = self.frame.as_mut() {
+            apply_style(frame, &self.cfg, &self.ctx);
+            self.cfg.label.as_ref().map(|s| frame.set_label(s.as_str()));
+            frame.redraw();
+        }
+    }
+
+    fn redraw(&mut self) {
+        self.frame.as_mut().map(|f| f.redraw());
+    }
+
+    // subscribe to our own ping
+    fn subscriptions(&self) -> Vec<String> {
+        vec![self.topic.clone()]
+    }
+
+    fn on_connection(&mut self, connected: bool) {
+        if !connected {
+            self.show_dead();
+        }
+    }
+
+    fn resize(&mut self, rect: Rect) {
+        self.cfg.rect = rect;
+        self.frame.as_mut().map(|f| f.resize(rect.x, rect.y, rect.w, rect.h));
+    }
+
+    fn teardown(&mut self) {
+        self.notifier.borrow_mut().clear_widget(&self.cfg);
+        self.frame.take().map(app::delete_widget);
+    }
+
     fn update(&mut self, event: &WidgetMsg) {
         match event {
-            WidgetMsg::Pub { topic, payload } => {
-                if *topic == self.topic {
-                    self.frame.as_mut().map( |mut f| f.set_color(Color::from_u32(0x00FF00)));
-                    self.last_update = SystemTime::now();
+            WidgetMsg::Pub { topic, payload, .. } => {
+                if !self.alive {
+                    self.alive = true;
+                    self.notifier.borrow_mut().clear_widget(&self.cfg);
                 }
+                let ok = self.ctx.color("ok", Color::from_u32(0x00FF00));
+                self.frame.as_mut().map( |f| f.set_color(ok));
+                self.last_update = Some(Instant::now());
             }
             WidgetMsg::Tick => {
+                // no verdict before the first ping had the time to come back
+                let timeout = self.cfg.timeout.map_or(
+                    2 * self.tick_period().unwrap_or(DEFAULT_TICK),
+                    |ms| Duration::from_millis(ms.max(0) as u64),
+                );
+                match self.last_update {
+                    Some(last) if last.elapsed() > timeout => self.show_dead(),
+                    Some(_) => {}
+                    None => self.last_update = Some(Instant::now()),
+                }
                 self.sinkref_cmd.push(PubSubCmd::Publish {
                     topic: self.topic.clone(),
                     payload: payload_encode("OK"),
                 });
-                if self.last_update.elapsed().unwrap().as_millis() > 1100 {
-                    self.frame.as_mut().map( |mut f| f.set_color(Color::from_u32(0xFF0000)));
-                } 
             }
         }
     }
@@ -0,0 +1,203 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use fltk::draw::{LineStyle, Rect};
+use fltk::enums::{Align, Color, Font, FrameType};
+use fltk::frame::Frame;
+use fltk::{app, draw, prelude::*};
+use log::warn;
+
+use crate::config::file_xml::WidgetParams;
+use crate::pubsub::payload_as_f64;
+use crate::pubsub::topic_matcher::TopicPattern;
+use crate::widget::theme::apply_style;
+use crate::widget::Context;
+
+use super::{PubSubWidget, WidgetMsg};
+
+#[derive(Debug, Default)]
+struct Headings {
+    heading: Option<f64>,
+    target: Option<f64>,
+}
+
+/// North-up compass rose with a needle for the heading on src and a dashed
+/// one for the target heading on the target topic. Headings are degrees,
+/// or radians with unit="rad", clockwise from north; offset is added to
+/// both, as declination or mounting correction.
+pub struct Compass {
+    cfg: WidgetParams,
+    ctx: Context,
+    frame: Option<Frame>,
+    target: Option<TopicPattern>,
+    headings: Rc<RefCell<Headings>>,
+}
+
+impl Compass {
+    pub fn new(cfg: &WidgetParams) -> Self {
+        let mut compass = Self {
+            cfg: cfg.clone(),
+            ctx: Context::current(),
+            frame: None,
+            target: None,
+            headings: Rc::new(RefCell::new(Headings::default())),
+        };
+        compass.compile_target();
+        compass
+    }
+
+    fn compile_target(&mut self) {
+        self.target = self.cfg.target.as_ref().and_then(|t| {
+            TopicPattern::compile(t)
+                .map_err(|e| warn!("Compass target {} : {}", t, e))
+                .ok()
+        });
+    }
+
+    /// Degrees in 0..360 after unit conversion and offset.
+    fn to_heading(&self, value: f64) -> f64 {
+        let degrees = if self.cfg.unit.as_deref() == Some("rad") {
+            value.to_degrees()
+        } else {
+            value
+        };
+        (degrees + self.cfg.offset.unwrap_or(0.0)).rem_euclid(360.0)
+    }
+
+    // the closure keeps a copy of the config, set again after set_config
+    fn set_draw(&mut self) {
+        let Some(frame) = self.frame.as_mut() else {
+            return;
+        };
+        let headings = self.headings.clone();
+        let ctx = self.ctx.clone();
+        let cfg = self.cfg.clone();
+        frame.draw(move |w| {
+            let headings = headings.borrow();
+            let (bg, fg) = (ctx.bg(&cfg), ctx.fg(&cfg));
+            let (cx, cy) = (w.x() + w.w() / 2, w.y() + w.h() / 2);
+            let radius = (w.w().min(w.h()) / 2 - 4).max(10);
+            let r = radius as f64;
+            draw::set_draw_color(bg);
+            draw::draw_pie(cx - radius, cy - radius, 2 * radius, 2 * radius, 0., 360.);
+            draw::set_draw_color(fg);
+            draw::set_line_style(LineStyle::Solid, 2);
+            draw::draw_circle(cx as f64, cy as f64, r);
+            for deg in (0..360).step_by(10) {
+                let len = if deg % 30 == 0 { r * 0.15 } else { r * 0.07 };
+                let (x1, y1) = polar(cx, cy, r, deg as f64);
+                let (x2, y2) = polar(cx, cy, r - len, deg as f64);
+                draw::draw_line(x1, y1, x2, y2);
+            }
+            draw::set_font(Font::HelveticaBold, (radius / 6).max(8));
+            for (cardinal, deg) in [("N", 0.), ("E", 90.), ("S", 180.), ("W", 270.)] {
+                let (x, y) = polar(cx, cy, r * 0.68, deg);
+                draw::draw_text2(cardinal, x - 10, y - 10, 20, 20, Align::Center);
+            }
+            if let Some(target) = headings.target {
+                let (x, y) = polar(cx, cy, r * 0.85, target);
+                draw::set_draw_color(ctx.color("target", Color::from_u32(0xFF8000)));
+                draw::set_line_style(LineStyle::Dash, 2);
+                draw::draw_line(cx, cy, x, y);
+            }
+            draw::set_line_style(LineStyle::Solid, 0);
+            if let Some(heading) = headings.heading {
+                draw_needle(cx, cy, r, heading, ctx.valuator_color, fg);
+                draw::set_draw_color(fg);
+                draw::draw_text2(
+                    &format!("{:.0}°", heading),
+                    cx - radius,
+                    cy + radius / 4,
+                    2 * radius,
+                    20,
+                    Align::Center,
+                );
+            }
+        });
+        frame.redraw();
+    }
+}
+
+// point at `radius` from the center in the direction of a compass heading
+fn polar(cx: i32, cy: i32, radius: f64, heading: f64) -> (i32, i32) {
+    let a = heading.to_radians();
+    (
+        cx + (radius * a.sin()).round() as i32,
+        cy - (radius * a.cos()).round() as i32,
+    )
+}
+
+fn draw_needle(cx: i32, cy: i32, r: f64, heading: f64, color: Color, fg: Color) {
+    let tip = polar(cx, cy, r * 0.8, heading);
+    let tail = polar(cx, cy, r * 0.5, heading + 180.0);
+    let left = polar(cx, cy, r * 0.08, heading - 90.0);
+    let right = polar(cx, cy, r * 0.08, heading + 90.0);
+    draw::set_draw_color(color);
+    draw::draw_polygon(tip.0, tip.1, left.0, left.1, right.0, right.1);
+    draw::set_draw_color(fg);
+    draw::draw_polygon(tail.0, tail.1, left.0, left.1, right.0, right.1);
+}
+
+impl PubSubWidget for Compass {
+    fn draw(&mut self) {
+        let r = self.cfg.rect;
+        let mut frame = Frame::new(r.x, r.y, r.w, r.h, None);
+        frame.set_frame(FrameType::NoBox);
+        frame.set_align(Align::Bottom | Align::Inside);
+        apply_style(&mut frame, &self.cfg, &self.ctx);
+        self.cfg.label.as_ref().map(|s| frame.set_label(s.as_str()));
+        self.frame = Some(frame);
+        self.set_draw();
+    }
+
+    fn get_config(&self) -> WidgetParams {
+        self.cfg.clone()
+    }
+
+    fn set_config(&mut self, cfg: WidgetParams) {
+        self.cfg = cfg;
+        self.compile_target();
+        if let Some(frame) = self.frame.as_mut() {
+            apply_style(frame, &self.cfg, &self.ctx);
+            frame.set_label(self.cfg.label.as_deref().unwrap_or(""));
+        }
+        self.set_draw();
+    }
+
+    fn redraw(&mut self) {
+        self.frame.as_mut().map(|f| f.redraw());
+    }
+
+    fn subscriptions(&self) -> Vec<String> {
+        self.cfg
+            .src_topic
+            .iter()
+            .chain(self.cfg.target.iter())
+            .cloned()
+            .collect()
+    }
+
+    fn resize(&mut self, rect: Rect) {
+        self.cfg.rect = rect;
+        self.frame.as_mut().map(|f| f.resize(rect.x, rect.y, rect.w, rect.h));
+    }
+
+    fn teardown(&mut self) {
+        self.frame.take().map(app::delete_widget);
+    }
+
+    fn update(&mut self, event: &WidgetMsg) {
+        if let WidgetMsg::Pub { topic, payload, .. } = event {
+            let Ok(value) = payload_as_f64(payload) else {
+                return;
+            };
+            let heading = self.to_heading(value);
+            let mut headings = self.headings.borrow_mut();
+            if self.target.as_ref().is_some_and(|t| t.matches(topic)) {
+                headings.target = Some(heading);
+            } else {
+                headings.heading = Some(heading);
+            }
+        }
+    }
+}
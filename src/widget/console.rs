@@ -0,0 +1,237 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Local};
+use fltk::browser::Browser;
+use fltk::button::ToggleButton;
+use fltk::draw::Rect;
+use fltk::enums::{CallbackTrigger, Color};
+use fltk::group::Group;
+use fltk::input::Input;
+use fltk::{app, prelude::*};
+
+use crate::config::file_xml::WidgetParams;
+use crate::logger::log_lines_since;
+use crate::pubsub::payload_display;
+use crate::widget::theme::apply_style;
+use crate::widget::Context;
+
+use super::{PubSubWidget, WidgetMsg};
+
+const DEFAULT_LINES: usize = 500;
+const BAR_HEIGHT: i32 = 25;
+const PAUSE_WIDTH: i32 = 80;
+// log records are picked up this often
+const POLL_PERIOD: Duration = Duration::from_millis(250);
+
+// colors for topic lines, picked by a hash of the topic
+const TOPIC_COLORS: [u32; 6] = [0x00A0FF, 0x00C080, 0xC080FF, 0xFF80C0, 0x80C0C0, 0xC0C000];
+
+#[derive(Debug, Clone)]
+enum Source {
+    Topic(String),
+    Log(log::Level),
+}
+
+#[derive(Debug, Clone)]
+struct ConsoleLine {
+    time: DateTime<Local>,
+    source: Source,
+    text: String,
+}
+
+#[derive(Debug, Default)]
+struct ConsoleState {
+    lines: VecDeque<ConsoleLine>,
+    filter: String,
+    paused: bool,
+}
+
+/// Scrolling console of the messages on src, shown with payload_display,
+/// and of the application's own log records. Keeps the last lines lines,
+/// shows only those containing the text of the filter box, and stops
+/// scrolling while paused. Log lines are colored by level, messages by topic.
+pub struct Console {
+    cfg: WidgetParams,
+    ctx: Context,
+    group: Option<Group>,
+    browser: Option<Browser>,
+    state: Rc<RefCell<ConsoleState>>,
+    log_seq: u64,
+    clock: fn() -> SystemTime,
+}
+
+impl Console {
+    pub fn new(cfg: &WidgetParams, clock: fn() -> SystemTime) -> Self {
+        Self {
+            cfg: cfg.clone(),
+            ctx: Context::current(),
+            group: None,
+            browser: None,
+            state: Rc::new(RefCell::new(ConsoleState::default())),
+            // only records logged from now on
+            log_seq: log_lines_since(0).last().map_or(0, |l| l.seq),
+            clock,
+        }
+    }
+
+    fn push(&mut self, line: ConsoleLine) {
+        let max = self.cfg.lines.unwrap_or(DEFAULT_LINES).max(1);
+        let mut state = self.state.borrow_mut();
+        if let (Some(browser), false) = (self.browser.as_mut(), state.paused) {
+            if matches_filter(&line, &state.filter) {
+                browser.add(&format_line(&line, &self.ctx, &self.cfg));
+                while browser.size() > max as i32 {
+                    browser.remove(1);
+                }
+                browser.bottom_line(browser.size());
+            }
+        }
+        state.lines.push_back(line);
+        while state.lines.len() > max {
+            state.lines.pop_front();
+        }
+    }
+
+    fn poll_log(&mut self) {
+        for record in log_lines_since(self.log_seq) {
+            self.log_seq = record.seq;
+            self.push(ConsoleLine {
+                time: record.time,
+                source: Source::Log(record.level),
+                text: record.text,
+            });
+        }
+    }
+}
+
+fn matches_filter(line: &ConsoleLine, filter: &str) -> bool {
+    if filter.is_empty() {
+        return true;
+    }
+    let filter = filter.to_lowercase();
+    let topic_match = match &line.source {
+        Source::Topic(topic) => topic.to_lowercase().contains(&filter),
+        Source::Log(_) => false,
+    };
+    topic_match || line.text.to_lowercase().contains(&filter)
+}
+
+fn format_line(line: &ConsoleLine, ctx: &Context, cfg: &WidgetParams) -> String {
+    let (color, source) = match &line.source {
+        Source::Log(level) => {
+            let color = match level {
+                log::Level::Error => ctx.color("alarm", Color::from_u32(0xFF0000)),
+                log::Level::Warn => ctx.color("warning", Color::from_u32(0xFFB000)),
+                log::Level::Info => ctx.fg(cfg),
+                _ => ctx.color("stale", Color::from_u32(0x808080)),
+            };
+            (color, level.to_string())
+        }
+        Source::Topic(topic) => {
+            let hash = topic.bytes().fold(0usize, |h, b| h.wrapping_mul(31).wrapping_add(b as usize));
+            (Color::from_u32(TOPIC_COLORS[hash % TOPIC_COLORS.len()]), topic.clone())
+        }
+    };
+    // @. ends the format codes, an @ in the text is shown as is
+    format!(
+        "@C{}@.{} {} {}",
+        color.bits(),
+        line.time.format("%H:%M:%S%.3f"),
+        source,
+        line.text
+    )
+}
+
+/// Shows the buffered lines that pass the filter.
+fn refill(browser: &mut Browser, state: &ConsoleState, ctx: &Context, cfg: &WidgetParams) {
+    browser.clear();
+    for line in state.lines.iter().filter(|l| matches_filter(l, &state.filter)) {
+        browser.add(&format_line(line, ctx, cfg));
+    }
+    browser.bottom_line(browser.size());
+}
+
+impl PubSubWidget for Console {
+    fn draw(&mut self) {
+        let r = self.cfg.rect;
+        let mut group = Group::new(r.x, r.y, r.w, r.h, None);
+        let mut filter = Input::new(r.x, r.y, r.w - PAUSE_WIDTH, BAR_HEIGHT, None);
+        filter.set_tooltip("Filter");
+        filter.set_trigger(CallbackTrigger::Changed);
+        let mut pause = ToggleButton::new(r.x + r.w - PAUSE_WIDTH, r.y, PAUSE_WIDTH, BAR_HEIGHT, "Pause");
+        let mut browser = Browser::new(r.x, r.y + BAR_HEIGHT, r.w, r.h - BAR_HEIGHT, None);
+        apply_style(&mut browser, &self.cfg, &self.ctx);
+        browser.set_text_size(self.cfg.text_size.unwrap_or(12));
+        group.end();
+        group.resizable(&browser);
+
+        filter.set_callback({
+            let state = self.state.clone();
+            let (ctx, cfg) = (self.ctx.clone(), self.cfg.clone());
+            let mut browser = browser.clone();
+            move |input| {
+                let mut state = state.borrow_mut();
+                state.filter = input.value();
+                refill(&mut browser, &state, &ctx, &cfg);
+            }
+        });
+        pause.set_callback({
+            let state = self.state.clone();
+            let (ctx, cfg) = (self.ctx.clone(), self.cfg.clone());
+            let mut browser = browser.clone();
+            move |button| {
+                let mut state = state.borrow_mut();
+                state.paused = button.value();
+                if !state.paused {
+                    refill(&mut browser, &state, &ctx, &cfg);
+                }
+            }
+        });
+        self.group = Some(group);
+        self.browser = Some(browser);
+    }
+
+    fn get_config(&self) -> WidgetParams {
+        self.cfg.clone()
+    }
+
+    fn set_config(&mut self, cfg: WidgetParams) {
+        self.cfg = cfg;
+        if let Some(browser) = self.browser.as_mut() {
+            apply_style(browser, &self.cfg, &self.ctx);
+            refill(browser, &self.state.borrow(), &self.ctx, &self.cfg);
+        }
+    }
+
+    fn redraw(&mut self) {
+        self.browser.as_mut().map(|b| b.redraw());
+    }
+
+    fn tick_period(&self) -> Option<Duration> {
+        Some(POLL_PERIOD)
+    }
+
+    fn resize(&mut self, rect: Rect) {
+        self.cfg.rect = rect;
+        self.group.as_mut().map(|g| g.resize(rect.x, rect.y, rect.w, rect.h));
+    }
+
+    fn teardown(&mut self) {
+        self.browser = None;
+        self.group.take().map(app::delete_widget);
+    }
+
+    fn update(&mut self, event: &WidgetMsg) {
+        match event {
+            WidgetMsg::Pub { topic, payload, .. } => self.push(ConsoleLine {
+                time: DateTime::<Local>::from((self.clock)()),
+                source: Source::Topic(topic.to_string()),
+                text: payload_display(payload),
+            }),
+            WidgetMsg::Tick => self.poll_log(),
+        }
+    }
+}
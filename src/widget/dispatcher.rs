@@ -0,0 +1,219 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::{error, info};
+use tokio::sync::mpsc::Receiver;
+
+use crate::alarm::AlarmManager;
+use crate::config::file_xml::WidgetParams;
+use crate::pubsub::router::TopicRouter;
+use crate::pubsub::PubSubEvent;
+use crate::widget::timers::WidgetTimers;
+use crate::widget::{PubSubWidget, WidgetMsg};
+
+// beyond this the messages wait for the next frame
+const MAX_MESSAGES_PER_FRAME: usize = 1000;
+
+/// Messages from the tokio side to the FLTK UI thread.
+#[derive(Debug, Clone)]
+pub enum UiMsg {
+    PubSub(PubSubEvent),
+    Config(WidgetParams),
+    Reload,
+}
+
+/// Rebuilds the widgets from the config file, see `Dispatcher::with_reloader`.
+pub type Reloader = Box<dyn FnMut(&mut Dispatcher) -> Result<(), String>>;
+
+/// Owns the widgets on the UI thread and applies the queued messages to them.
+/// Widgets that received anything are remembered as dirty and redrawn once
+/// per frame, however many updates they got.
+pub struct Dispatcher {
+    widgets: Vec<Box<dyn PubSubWidget>>,
+    router: TopicRouter,
+    dirty: Vec<bool>,
+    timers: WidgetTimers,
+    reloader: Option<Reloader>,
+    alarms: Option<Rc<RefCell<AlarmManager>>>,
+}
+
+impl Dispatcher {
+    pub fn new(widgets: Vec<Box<dyn PubSubWidget>>) -> Self {
+        let router = build_router(&widgets);
+        let dirty = vec![false; widgets.len()];
+        let timers = WidgetTimers::new(&widgets);
+        Self {
+            widgets,
+            router,
+            dirty,
+            timers,
+            reloader: None,
+            alarms: None,
+        }
+    }
+
+    /// The reloader runs on `UiMsg::Reload`, it is expected to call
+    /// `teardown` and `set_widgets` once the new config is known to be valid.
+    pub fn with_reloader(mut self, reloader: Reloader) -> Self {
+        self.reloader = Some(reloader);
+        self
+    }
+
+    /// Every message is also evaluated against the alarm rules, which are
+    /// checked for timeouts with the widget timers.
+    pub fn with_alarms(mut self, alarms: Rc<RefCell<AlarmManager>>) -> Self {
+        self.alarms = Some(alarms);
+        self
+    }
+
+    /// Redraws the widgets changed since the last frame, returns their count.
+    pub fn redraw_dirty(&mut self) -> usize {
+        let mut count = 0;
+        for (widget, dirty) in self.widgets.iter_mut().zip(self.dirty.iter_mut()) {
+            if *dirty {
+                widget.redraw();
+                *dirty = false;
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Sends a Tick to the widgets whose timer expired, returns their count.
+    pub fn fire_timers(&mut self) -> usize {
+        let now = Instant::now();
+        if let Some(alarms) = self.alarms.as_ref() {
+            alarms.borrow_mut().check(now);
+        }
+        let expired = self.timers.expired(now);
+        for idx in expired.iter() {
+            self.widgets[*idx].update(&WidgetMsg::Tick);
+            self.dirty[*idx] = true;
+        }
+        expired.len()
+    }
+
+    /// Applies what was queued since the last frame, at most
+    /// MAX_MESSAGES_PER_FRAME so a flood can't freeze the UI, the rest waits
+    /// for the next frame. Returns the count.
+    pub fn drain(&mut self, receiver: &mut Receiver<UiMsg>) -> usize {
+        let mut count = 0;
+        while count < MAX_MESSAGES_PER_FRAME {
+            let Ok(msg) = receiver.try_recv() else {
+                break;
+            };
+            self.dispatch(msg);
+            count += 1;
+        }
+        count
+    }
+
+    /// The shortest Tick period of the widgets.
+    pub fn shortest_tick(&self) -> Option<Duration> {
+        self.timers.shortest()
+    }
+
+    pub fn dispatch(&mut self, msg: UiMsg) {
+        match msg {
+            UiMsg::PubSub(PubSubEvent::Publish { topic, payload }) => {
+                if let Some(alarms) = self.alarms.as_ref() {
+                    alarms.borrow_mut().on_message(&topic, &payload);
+                }
+                let routes = self.router.route(&topic);
+                if !routes.is_empty() {
+                    let topic = Arc::new(topic);
+                    let payload = Arc::new(payload);
+                    for (idx, captures) in routes {
+                        self.dirty[idx] = true;
+                        self.widgets[idx].update(&WidgetMsg::Pub {
+                            topic: topic.clone(),
+                            payload: payload.clone(),
+                            captures,
+                        });
+                    }
+                }
+            }
+            UiMsg::PubSub(event) => {
+                let connected = matches!(event, PubSubEvent::Connected);
+                info!("PubSub {}", if connected { "connected" } else { "disconnected" });
+                for widget in self.widgets.iter_mut() {
+                    widget.on_connection(connected);
+                }
+                self.dirty.iter_mut().for_each(|d| *d = true);
+            }
+            UiMsg::Config(cfg) => {
+                let now = Instant::now();
+                for (idx, (widget, dirty)) in self.widgets.iter_mut().zip(self.dirty.iter_mut()).enumerate() {
+                    let old = widget.get_config();
+                    if old.path == cfg.path {
+                        let rect = cfg.rect;
+                        widget.set_config(cfg.clone());
+                        if old.rect != rect {
+                            widget.resize(rect);
+                        }
+                        *dirty = true;
+                        // the other widgets keep their deadlines
+                        self.timers.rearm(idx, widget.tick_period(), now);
+                    }
+                }
+                self.router = build_router(&self.widgets);
+            }
+            UiMsg::Reload => {
+                if let Some(mut reloader) = self.reloader.take() {
+                    info!("Reloading dashboard");
+                    if let Err(e) = reloader(self) {
+                        error!("Reload failed, keeping the current dashboard : {}", e);
+                    }
+                    self.reloader = Some(reloader);
+                }
+            }
+        }
+    }
+
+    /// Removes all widgets, returns their saved state by config path.
+    pub fn teardown(&mut self) -> BTreeMap<Vec<usize>, Vec<u8>> {
+        let mut states = BTreeMap::new();
+        for mut widget in self.widgets.drain(..) {
+            if let Some(state) = widget.save_state() {
+                states.insert(widget.get_config().path, state);
+            }
+            widget.teardown();
+        }
+        self.dirty.clear();
+        self.router = TopicRouter::new();
+        self.timers = WidgetTimers::new(&self.widgets);
+        states
+    }
+
+    /// Installs new widgets and hands them the state saved by `teardown`.
+    pub fn set_widgets(
+        &mut self,
+        widgets: Vec<Box<dyn PubSubWidget>>,
+        states: BTreeMap<Vec<usize>, Vec<u8>>,
+    ) {
+        self.widgets = widgets;
+        for widget in self.widgets.iter_mut() {
+            if let Some(state) = states.get(&widget.get_config().path) {
+                widget.restore_state(state);
+            }
+        }
+        self.dirty = vec![true; self.widgets.len()];
+        self.router = build_router(&self.widgets);
+        self.timers = WidgetTimers::new(&self.widgets);
+    }
+}
+
+/// Indexes the subscriptions of every widget by its position in `widgets`.
+fn build_router(widgets: &[Box<dyn PubSubWidget>]) -> TopicRouter {
+    let mut router = TopicRouter::new();
+    for (idx, widget) in widgets.iter().enumerate() {
+        for topic in widget.subscriptions() {
+            router.add(&topic, idx);
+        }
+    }
+    router.log_stats();
+    router
+}
@@ -0,0 +1,284 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use fltk::draw::Rect;
+use fltk::enums::{Color, Event, FrameType, Key, Shortcut};
+use fltk::frame::Frame;
+use fltk::widget::Widget;
+use fltk::{app, draw, prelude::*};
+use log::{error, info};
+
+use crate::config::file_xml::{save_layout, WidgetParams};
+use crate::limero::{SinkRef, SinkTrait};
+use crate::widget::property_editor::open_property_editor;
+use crate::widget::Context;
+
+// distance from the bottom-right corner within which a drag resizes
+const RESIZE_BORDER: i32 = 10;
+
+/// The fltk widgets created for one configured widget.
+struct EditItem {
+    cfg: WidgetParams,
+    parts: Vec<Widget>,
+    // dragged or resized, only then its geometry is saved
+    moved: bool,
+}
+
+struct Drag {
+    item: usize,
+    mouse: (i32, i32),
+    rect: Rect,
+    parts: Vec<Rect>,
+    resize: bool,
+    moved: bool,
+}
+
+/// Layout editor: Ctrl+E toggles edit mode, in which the right mouse button
+/// moves a widget or resizes it from its bottom-right corner, and a right
+/// click opens the property editor. Ctrl+S writes everything back to the
+/// config file.
+pub struct Editor {
+    items: Vec<EditItem>,
+    enabled: bool,
+    drag: Option<Drag>,
+    config_file: String,
+    ctx: Context,
+    changes: SinkRef<WidgetParams>,
+}
+
+impl Editor {
+    pub fn new(config_file: &str, ctx: &Context, changes: SinkRef<WidgetParams>) -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self {
+            items: Vec::new(),
+            enabled: false,
+            drag: None,
+            config_file: config_file.to_string(),
+            ctx: ctx.clone(),
+            changes,
+        }))
+    }
+
+    /// The config of the widget at `path` in the config file, None once a
+    /// reload removed it.
+    pub fn config(&self, path: &[usize]) -> Option<WidgetParams> {
+        self.items
+            .iter()
+            .find(|i| i.cfg.path == path)
+            .map(|i| i.cfg.clone())
+    }
+
+    /// Stores the new config and hands it to the running widget, returns
+    /// false when the widget is gone.
+    pub fn set_config(&mut self, path: &[usize], cfg: WidgetParams) -> bool {
+        let Some(item) = self.items.iter_mut().find(|i| i.cfg.path == path) else {
+            return false;
+        };
+        item.cfg = cfg.clone();
+        self.changes.push(cfg);
+        true
+    }
+
+    /// Forgets all widgets, before the dashboard is rebuilt.
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.drag = None;
+    }
+
+    pub fn item_count(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Forgets the widgets registered after the first `len`, those of a
+    /// rebuild that failed.
+    pub fn truncate(&mut self, len: usize) {
+        self.items.truncate(len);
+        self.drag = None;
+    }
+
+    /// Forgets the first `count` widgets, those replaced by a rebuild.
+    pub fn forget_first(&mut self, count: usize) {
+        self.items.drain(..count.min(self.items.len()));
+        self.drag = None;
+    }
+
+    pub fn register(&mut self, cfg: &WidgetParams, parts: Vec<Widget>) {
+        if !parts.is_empty() {
+            self.items.push(EditItem {
+                cfg: cfg.clone(),
+                parts,
+                moved: false,
+            });
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let widgets: Vec<WidgetParams> = self.items.iter().map(|i| i.cfg.clone()).collect();
+        let moved: Vec<Vec<usize>> = self
+            .items
+            .iter()
+            .filter(|i| i.moved)
+            .map(|i| i.cfg.path.clone())
+            .collect();
+        save_layout(&self.config_file, &widgets, &moved)
+    }
+
+    fn snap(&self, item: usize, rect: Rect) -> Rect {
+        let cell = self.items.get(item).and_then(|i| i.cfg.grid_cell);
+        let (gx, gy, gw, gh) = match cell {
+            Some(cell) => (cell.x, cell.y, cell.w, cell.h),
+            None => (0, 0, self.ctx.grid_width, self.ctx.grid_height),
+        };
+        // a zero cell would divide by zero, leave the rect as it is
+        if gw < 1 || gh < 1 {
+            return rect;
+        }
+        let round = |v: i32, origin: i32, grid: i32| origin + ((v - origin + grid / 2) / grid) * grid;
+        Rect::new(
+            round(rect.x, gx, gw),
+            round(rect.y, gy, gh),
+            round(rect.w, 0, gw).max(gw),
+            round(rect.h, 0, gh).max(gh),
+        )
+    }
+
+    fn item_at(&self, x: i32, y: i32) -> Option<usize> {
+        self.items.iter().rposition(|item| {
+            let r = item.cfg.rect;
+            item.parts.iter().any(|p| p.visible_r())
+                && x >= r.x
+                && x < r.x + r.w
+                && y >= r.y
+                && y < r.y + r.h
+        })
+    }
+
+    fn start_drag(&mut self, x: i32, y: i32) -> bool {
+        match self.item_at(x, y) {
+            Some(idx) => {
+                let item = &self.items[idx];
+                let r = item.cfg.rect;
+                self.drag = Some(Drag {
+                    item: idx,
+                    mouse: (x, y),
+                    rect: r,
+                    parts: item
+                        .parts
+                        .iter()
+                        .map(|p| Rect::new(p.x(), p.y(), p.w(), p.h()))
+                        .collect(),
+                    resize: x > r.x + r.w - RESIZE_BORDER && y > r.y + r.h - RESIZE_BORDER,
+                    moved: false,
+                });
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn drag_to(&mut self, x: i32, y: i32) {
+        if let Some(drag) = self.drag.as_mut() {
+            drag.moved = true;
+        }
+        let Some(drag) = self.drag.as_ref() else {
+            return;
+        };
+        let (dx, dy) = (x - drag.mouse.0, y - drag.mouse.1);
+        let r = drag.rect;
+        let wanted = if drag.resize {
+            Rect::new(r.x, r.y, r.w + dx, r.h + dy)
+        } else {
+            Rect::new(r.x + dx, r.y + dy, r.w, r.h)
+        };
+        let new = self.snap(drag.item, wanted);
+        let (mx, my, mw, mh) = (new.x - r.x, new.y - r.y, new.w - r.w, new.h - r.h);
+        let Some(item) = self.items.get_mut(drag.item) else {
+            return;
+        };
+        for (part, orig) in item.parts.iter_mut().zip(drag.parts.iter()) {
+            part.resize(orig.x + mx, orig.y + my, orig.w + mw, orig.h + mh);
+        }
+        item.cfg.rect = new;
+    }
+}
+
+/// Creates the transparent overlay that catches mouse events in edit mode.
+/// It must be the last child of the window so it sits on top of all widgets.
+pub fn attach_editor(editor_ref: Rc<RefCell<Editor>>, rect: Rect) {
+    let mut overlay = Frame::new(rect.x, rect.y, rect.w, rect.h, None);
+    overlay.set_frame(FrameType::NoBox);
+    overlay.draw({
+        let editor = editor_ref.clone();
+        move |_| {
+            let editor = editor.borrow();
+            if !editor.enabled {
+                return;
+            }
+            for item in editor.items.iter().filter(|i| i.parts.iter().any(|p| p.visible_r())) {
+                let r = item.cfg.rect;
+                draw::draw_rect_with_color(r.x, r.y, r.w, r.h, Color::Red);
+                draw::draw_rect_fill(
+                    r.x + r.w - RESIZE_BORDER,
+                    r.y + r.h - RESIZE_BORDER,
+                    RESIZE_BORDER,
+                    RESIZE_BORDER,
+                    Color::Red,
+                );
+            }
+        }
+    });
+    overlay.handle(move |w, ev| {
+        let mut editor = editor_ref.borrow_mut();
+        match ev {
+            Event::Shortcut if app::event_state().contains(Shortcut::Ctrl) => {
+                if app::event_key() == Key::from_char('e') {
+                    editor.enabled = !editor.enabled;
+                    info!("Edit mode {}", if editor.enabled { "on" } else { "off" });
+                    w.top_window().map(|mut win| win.redraw());
+                    true
+                } else if app::event_key() == Key::from_char('s') && editor.enabled {
+                    if let Err(e) = editor.save() {
+                        error!("Saving layout failed : {}", e);
+                    }
+                    true
+                } else {
+                    false
+                }
+            }
+            Event::Push
+                if editor.enabled && app::event_mouse_button() == app::MouseButton::Right =>
+            {
+                let (x, y) = app::event_coords();
+                editor.start_drag(x, y)
+            }
+            Event::Drag if editor.drag.is_some() => {
+                let (x, y) = app::event_coords();
+                editor.drag_to(x, y);
+                w.top_window().map(|mut win| win.redraw());
+                true
+            }
+            Event::Released if editor.drag.is_some() => {
+                let drag = editor.drag.take().unwrap();
+                let Some(cfg) = editor.items.get(drag.item).map(|i| i.cfg.clone()) else {
+                    return true;
+                };
+                if drag.moved {
+                    editor.items[drag.item].moved = true;
+                    // the widget learns its new rect through set_config
+                    let path = cfg.path.clone();
+                    editor.set_config(&path, cfg);
+                    drop(editor);
+                } else {
+                    drop(editor);
+                    open_property_editor(editor_ref.clone(), &cfg.path);
+                }
+                w.top_window().map(|mut win| win.redraw());
+                true
+            }
+            _ => false,
+        }
+    });
+}
@@ -0,0 +1,131 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use fltk::draw::Rect;
+use fltk::enums::{Color, FrameType};
+use fltk::frame::Frame;
+use fltk::{app, draw, prelude::*};
+
+use crate::config::file_xml::WidgetParams;
+use crate::pubsub::payload_as_f64;
+use crate::widget::meter::{draw_label, range, MeterValue};
+use crate::widget::theme::apply_style;
+use crate::widget::Context;
+
+use super::{PubSubWidget, WidgetMsg};
+
+const DEFAULT_SEGMENTS: usize = 10;
+const GAP: i32 = 2;
+
+/// Bar graph of LED segments, vertical when higher than wide. Segments
+/// above the amber and red levels light in those colors, by default at 70%
+/// and 90% of min..max. The value on src is clamped to min..max and dims to
+/// the stale color after timeout msec.
+pub struct LedBar {
+    cfg: WidgetParams,
+    ctx: Context,
+    frame: Option<Frame>,
+    value: Rc<RefCell<MeterValue>>,
+}
+
+impl LedBar {
+    pub fn new(cfg: &WidgetParams) -> Self {
+        Self {
+            cfg: cfg.clone(),
+            ctx: Context::current(),
+            frame: None,
+            value: Rc::new(RefCell::new(MeterValue::new())),
+        }
+    }
+
+    fn set_draw(&mut self) {
+        let Some(frame) = self.frame.as_mut() else {
+            return;
+        };
+        let cfg = self.cfg.clone();
+        let ctx = self.ctx.clone();
+        let value = self.value.clone();
+        frame.draw(move |w| {
+            let value = value.borrow();
+            let (x, y, width, h) = draw_label(&cfg, &ctx, (w.x() + 2, w.y() + 2, w.w() - 4, w.h() - 4));
+            let (min, max) = range(&cfg);
+            let amber = cfg.amber.unwrap_or(min + 0.7 * (max - min));
+            let red = cfg.red.unwrap_or(min + 0.9 * (max - min));
+            let count = cfg.segments.unwrap_or(DEFAULT_SEGMENTS).max(1);
+            let lit = (value.fraction(&cfg) * count as f64).round() as usize;
+            let vertical = h > width;
+            for idx in 0..count {
+                // the level at the top of the segment decides its color
+                let level = min + (idx + 1) as f64 / count as f64 * (max - min);
+                let color = if level > red {
+                    ctx.color("alarm", Color::from_u32(0xFF0000))
+                } else if level > amber {
+                    ctx.color("warning", Color::from_u32(0xFFB000))
+                } else {
+                    ctx.color("ok", Color::from_u32(0x00C000))
+                };
+                let color = if idx < lit {
+                    value.color(&ctx, color)
+                } else {
+                    color.darker().darker().darker()
+                };
+                let i = idx as i32;
+                let n = count as i32;
+                if vertical {
+                    let sh = h / n;
+                    draw::draw_rect_fill(x, y + h - (i + 1) * sh, width, sh - GAP, color);
+                } else {
+                    let sw = width / n;
+                    draw::draw_rect_fill(x + i * sw, y, sw - GAP, h, color);
+                }
+            }
+        });
+    }
+}
+
+impl PubSubWidget for LedBar {
+    fn draw(&mut self) {
+        let r = self.cfg.rect;
+        let mut frame = Frame::new(r.x, r.y, r.w, r.h, None);
+        frame.set_frame(FrameType::FlatBox);
+        apply_style(&mut frame, &self.cfg, &self.ctx);
+        self.frame = Some(frame);
+        self.set_draw();
+    }
+
+    fn get_config(&self) -> WidgetParams {
+        self.cfg.clone()
+    }
+
+    fn set_config(&mut self, cfg: WidgetParams) {
+        self.cfg = cfg;
+        if let Some(frame) = self.frame.as_mut() {
+            apply_style(frame, &self.cfg, &self.ctx);
+        }
+        self.set_draw();
+    }
+
+    fn redraw(&mut self) {
+        self.frame.as_mut().map(|f| f.redraw());
+    }
+
+    fn resize(&mut self, rect: Rect) {
+        self.cfg.rect = rect;
+        self.frame.as_mut().map(|f| f.resize(rect.x, rect.y, rect.w, rect.h));
+    }
+
+    fn teardown(&mut self) {
+        self.frame.take().map(app::delete_widget);
+    }
+
+    fn update(&mut self, event: &WidgetMsg) {
+        match event {
+            WidgetMsg::Pub { payload, .. } => {
+                if let Ok(v) = payload_as_f64(payload) {
+                    self.value.borrow_mut().set(&self.cfg, v);
+                }
+            }
+            WidgetMsg::Tick => self.value.borrow_mut().tick(&self.cfg),
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use fltk::button::Button;
+use fltk::draw::Rect;
+use fltk::enums::{Color, Font};
+use fltk::group::Group;
+use fltk::misc::Progress;
+use fltk::{app, prelude::*};
+use log::{info, warn};
+
+use crate::config::file_xml::WidgetParams;
+use crate::limero::{SinkRef, SinkTrait};
+use crate::pubsub::topic_matcher::TopicPattern;
+use crate::pubsub::{payload_display, PubSubCmd};
+use crate::widget::pub_button::to_cbor;
+use crate::widget::theme::apply_style;
+use crate::widget::Context;
+
+use super::{PubSubWidget, WidgetMsg};
+
+const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_millis(5000);
+// resolution of the delays and timeouts
+const DEFAULT_TICK: Duration = Duration::from_millis(100);
+const PROGRESS_HEIGHT: i32 = 8;
+
+/// A <Step> of the sequence.
+#[derive(Debug)]
+struct Step {
+    label: Option<String>,
+    publish: Option<(String, String)>,
+    wait: Option<(TopicPattern, String)>,
+    timeout: Duration,
+    delay: Duration,
+}
+
+impl Step {
+    fn from_params(step: &WidgetParams, cfg: &WidgetParams) -> Self {
+        let wait_topic = step.src_topic.as_ref().or(cfg.src_topic.as_ref());
+        let wait = match (wait_topic, step.wait.as_ref()) {
+            (Some(topic), Some(value)) => TopicPattern::compile(topic)
+                .map(|pattern| (pattern, value.clone()))
+                .map_err(|e| warn!("MacroButton step wait on {} : {}", topic, e))
+                .ok(),
+            _ => None,
+        };
+        Self {
+            label: step.label.clone(),
+            publish: step.dst_topic.clone().zip(step.value.clone()),
+            wait,
+            timeout: step
+                .timeout
+                .or(cfg.timeout)
+                .map_or(DEFAULT_WAIT_TIMEOUT, |ms| Duration::from_millis(ms.max(0) as u64)),
+            delay: Duration::from_millis(step.delay.unwrap_or(0).max(0) as u64),
+        }
+    }
+}
+
+/// Where the sequence is, shared with the button callback.
+#[derive(Debug, Clone)]
+enum Phase {
+    Idle,
+    Publish(usize),
+    Wait { step: usize, deadline: Instant },
+    Delay { step: usize, until: Instant },
+    Done(Result<(), String>),
+}
+
+/// Runs a sequence of <Step> children on a click : each step publishes
+/// value to its dst, then optionally waits up to timeout msec for the value
+/// wait on its src (or the button's src), then waits delay msec before the
+/// next step. Progress shows in the label and a bar below the button; a
+/// click during the sequence cancels it.
+pub struct MacroButton {
+    cfg: WidgetParams,
+    ctx: Context,
+    group: Option<Group>,
+    button: Option<Button>,
+    progress: Option<Progress>,
+    steps: Vec<Step>,
+    phase: Rc<RefCell<Phase>>,
+    pubsub_cmd: SinkRef<PubSubCmd>,
+}
+
+impl MacroButton {
+    pub fn new(cfg: &WidgetParams, pubsub_cmd: SinkRef<PubSubCmd>) -> Self {
+        let mut button = Self {
+            cfg: cfg.clone(),
+            ctx: Context::current(),
+            group: None,
+            button: None,
+            progress: None,
+            steps: Vec::new(),
+            phase: Rc::new(RefCell::new(Phase::Idle)),
+            pubsub_cmd,
+        };
+        button.configure();
+        button
+    }
+
+    fn configure(&mut self) {
+        self.steps = self
+            .cfg
+            .children
+            .iter()
+            .filter(|c| c.name == "Step")
+            .map(|step| Step::from_params(step, &self.cfg))
+            .collect();
+    }
+
+    fn label(&self) -> String {
+        self.cfg.label.clone().unwrap_or_default()
+    }
+
+    /// Moves the sequence on as far as the clock allows.
+    fn advance(&mut self, now: Instant) {
+        loop {
+            let phase = self.phase.borrow().clone();
+            let next = match phase {
+                Phase::Publish(idx) if idx >= self.steps.len() => {
+                    info!("MacroButton {} done", self.label());
+                    Phase::Done(Ok(()))
+                }
+                Phase::Publish(idx) => {
+                    let step = &self.steps[idx];
+                    if let Some((dst, value)) = step.publish.as_ref() {
+                        info!("MacroButton {} step {} : {} = {}", self.label(), idx + 1, dst, value);
+                        self.pubsub_cmd.push(PubSubCmd::Publish {
+                            topic: dst.clone(),
+                            payload: to_cbor(value),
+                        });
+                    }
+                    match step.wait {
+                        Some(_) => Phase::Wait {
+                            step: idx,
+                            deadline: now + step.timeout,
+                        },
+                        None => Phase::Delay {
+                            step: idx,
+                            until: now + step.delay,
+                        },
+                    }
+                }
+                Phase::Wait { step, deadline } if now >= deadline => {
+                    let expected = self.steps[step].wait.as_ref().map_or("", |w| w.1.as_str());
+                    let error = format!("no {} at step {}", expected, step + 1);
+                    warn!("MacroButton {} : {}", self.label(), error);
+                    Phase::Done(Err(error))
+                }
+                Phase::Delay { step, until } if now >= until => Phase::Publish(step + 1),
+                _ => break,
+            };
+            *self.phase.borrow_mut() = next;
+        }
+        self.show();
+    }
+
+    fn show(&mut self) {
+        let label = self.label();
+        let count = self.steps.len();
+        let step_label = |idx: usize| {
+            let name = self.steps.get(idx).and_then(|s| s.label.clone());
+            format!("{} {}/{} {}", label, idx + 1, count, name.unwrap_or_default())
+        };
+        let pending = self.ctx.color("pending", Color::from_u32(0xFFC000));
+        let (text, color, done) = match &*self.phase.borrow() {
+            Phase::Idle => (label.clone(), self.ctx.color("button", Color::Blue), 0),
+            Phase::Publish(idx) | Phase::Wait { step: idx, .. } => (step_label(*idx), pending, *idx),
+            Phase::Delay { step, .. } => (step_label(*step), pending, step + 1),
+            Phase::Done(Ok(())) => (format!("{} done", label), self.ctx.color("ok", Color::from_u32(0x00C000)), count),
+            Phase::Done(Err(e)) => (
+                format!("{} : {}", label, e),
+                self.ctx.color("alarm", Color::from_u32(0xFF0000)),
+                0,
+            ),
+        };
+        if let Some(button) = self.button.as_mut() {
+            if self.cfg.bg.is_none() {
+                button.set_color(color);
+            }
+            button.set_label(text.trim_end());
+        }
+        if let Some(progress) = self.progress.as_mut() {
+            progress.set_maximum(count.max(1) as f64);
+            progress.set_value(done as f64);
+        }
+    }
+}
+
+/// Matches a payload against a wait value, as encoded or as shown.
+fn matches_value(payload: &Vec<u8>, expected: &str) -> bool {
+    *payload == to_cbor(expected) || payload_display(payload) == expected
+}
+
+impl PubSubWidget for MacroButton {
+    fn draw(&mut self) {
+        let r = self.cfg.rect;
+        let group = Group::new(r.x, r.y, r.w, r.h, None);
+        let mut button = Button::new(r.x, r.y, r.w, r.h - PROGRESS_HEIGHT, None);
+        button.set_label_font(Font::HelveticaBold);
+        apply_style(&mut button, &self.cfg, &self.ctx);
+        if self.cfg.fg.is_none() {
+            button.set_label_color(self.ctx.color("button_text", Color::White));
+        }
+        let mut progress = Progress::new(r.x, r.y + r.h - PROGRESS_HEIGHT, r.w, PROGRESS_HEIGHT, None);
+        progress.set_minimum(0.0);
+        progress.set_selection_color(self.ctx.valuator_color);
+        group.end();
+        button.set_callback({
+            let phase = self.phase.clone();
+            let label = self.label();
+            move |_| {
+                let mut phase = phase.borrow_mut();
+                *phase = match *phase {
+                    Phase::Publish(_) | Phase::Wait { .. } | Phase::Delay { .. } => {
+                        info!("MacroButton {} cancelled", label);
+                        Phase::Done(Err("cancelled".to_string()))
+                    }
+                    // the next tick publishes the first step
+                    _ => Phase::Publish(0),
+                };
+            }
+        });
+        self.group = Some(group);
+        self.button = Some(button);
+        self.progress = Some(progress);
+        self.show();
+    }
+
+    fn get_config(&self) -> WidgetParams {
+        self.cfg.clone()
+    }
+
+    fn set_config(&mut self, cfg: WidgetParams) {
+        self.cfg = cfg;
+        // the steps may have changed under a running sequence
+        *self.phase.borrow_mut() = Phase::Idle;
+        self.configure();
+        if let Some(button) = self.button.as_mut() {
+            apply_style(button, &self.cfg, &self.ctx);
+        }
+        self.show();
+    }
+
+    fn redraw(&mut self) {
+        self.group.as_mut().map(|g| g.redraw());
+    }
+
+    fn subscriptions(&self) -> Vec<String> {
+        let mut topics: Vec<String> = self
+            .cfg
+            .src_topic
+            .iter()
+            .chain(self.cfg.children.iter().filter_map(|c| c.src_topic.as_ref()))
+            .cloned()
+            .collect();
+        topics.sort();
+        topics.dedup();
+        topics
+    }
+
+    fn tick_period(&self) -> Option<Duration> {
+        Some(self.cfg.msec.map_or(DEFAULT_TICK, |ms| Duration::from_millis(ms.max(1) as u64)))
+    }
+
+    fn resize(&mut self, rect: Rect) {
+        self.cfg.rect = rect;
+        self.group.as_mut().map(|g| g.resize(rect.x, rect.y, rect.w, rect.h));
+    }
+
+    fn teardown(&mut self) {
+        self.button = None;
+        self.progress = None;
+        self.group.take().map(app::delete_widget);
+    }
+
+    fn update(&mut self, event: &WidgetMsg) {
+        let now = Instant::now();
+        match event {
+            WidgetMsg::Pub { topic, payload, .. } => {
+                let phase = self.phase.borrow().clone();
+                if let Phase::Wait { step, .. } = phase {
+                    let done = self.steps[step]
+                        .wait
+                        .as_ref()
+                        .is_some_and(|(pattern, value)| pattern.matches(topic) && matches_value(payload, value));
+                    if done {
+                        let until = now + self.steps[step].delay;
+                        *self.phase.borrow_mut() = Phase::Delay { step, until };
+                        self.advance(now);
+                    }
+                }
+            }
+            WidgetMsg::Tick => self.advance(now),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use std::time::{Duration, Instant};
+
+use fltk::draw;
+use fltk::enums::{Align, Color};
+
+use crate::config::file_xml::WidgetParams;
+use crate::widget::Context;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(3000);
+const LABEL_HEIGHT: i32 = 20;
+
+/// The value shown by a drawn meter, shared with its draw closure.
+/// Values are clamped to min..max, and become stale when nothing arrived
+/// for timeout msec.
+#[derive(Debug)]
+pub struct MeterValue {
+    pub value: Option<f64>,
+    pub stale: bool,
+    last_update: Instant,
+}
+
+impl MeterValue {
+    pub fn new() -> Self {
+        Self {
+            value: None,
+            stale: false,
+            last_update: Instant::now(),
+        }
+    }
+
+    pub fn set(&mut self, cfg: &WidgetParams, value: f64) {
+        let (min, max) = range(cfg);
+        self.value = Some(value.clamp(min, max));
+        self.stale = false;
+        self.last_update = Instant::now();
+    }
+
+    pub fn tick(&mut self, cfg: &WidgetParams) {
+        let timeout = cfg
+            .timeout
+            .map_or(DEFAULT_TIMEOUT, |ms| Duration::from_millis(ms as u64));
+        self.stale = self.value.is_some() && self.last_update.elapsed() > timeout;
+    }
+
+    /// Where the value is in min..max, from 0 to 1.
+    pub fn fraction(&self, cfg: &WidgetParams) -> f64 {
+        let (min, max) = range(cfg);
+        self.value.map_or(0.0, |v| (v - min) / (max - min))
+    }
+
+    /// The lit color, or the stale color for an old value.
+    pub fn color(&self, ctx: &Context, lit: Color) -> Color {
+        if self.stale {
+            ctx.color("stale", Color::from_u32(0x808080))
+        } else {
+            lit
+        }
+    }
+}
+
+pub fn range(cfg: &WidgetParams) -> (f64, f64) {
+    let min = cfg.min.unwrap_or(0.0);
+    let max = cfg.max.unwrap_or(100.0);
+    if max > min {
+        (min, max)
+    } else {
+        (min, min + 1.0)
+    }
+}
+
+/// Draws the label at the top of the rect, returns the rect left below it.
+pub fn draw_label(cfg: &WidgetParams, ctx: &Context, (x, y, w, h): (i32, i32, i32, i32)) -> (i32, i32, i32, i32) {
+    match cfg.label.as_ref() {
+        Some(label) if h > 2 * LABEL_HEIGHT => {
+            draw::set_draw_color(ctx.fg(cfg));
+            draw::set_font(draw::font(), cfg.text_size.unwrap_or(14));
+            draw::draw_text2(label, x, y, w, LABEL_HEIGHT, Align::Center);
+            (x, y + LABEL_HEIGHT, w, h - LABEL_HEIGHT)
+        }
+        _ => (x, y, w, h),
+    }
+}
@@ -1,3 +1,4 @@
+use crate::config::file_xml::WidgetParams;
 use crate::pubsub::PubSubEvent;
 use fltk::button::Button;
 use fltk::enums;
@@ -9,15 +10,41 @@ use serde_derive::Deserialize;
 use serde_derive::Serialize;
 use serde_yaml::Value;
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::mpsc;
 use tokio::sync::RwLock;
 
 // pub mod gauge;
+pub mod stats;
 pub mod sub_gauge ;
+pub mod sub_image;
 pub mod sub_label;
 pub mod pub_button;
+pub mod pub_input;
+pub mod alarm_panel;
+pub mod bar_chart;
 pub mod broker_alive;
+pub mod compass;
+pub mod console;
+pub mod dispatcher;
+pub mod edit;
+pub mod led_bar;
+pub mod macro_button;
+pub mod meter;
+pub mod notify;
+pub mod pages;
+pub mod property_editor;
+pub mod registry;
+pub mod scheduler;
+pub mod setpoint;
+pub mod seven_segment;
+pub mod switch;
+pub mod theme;
+pub mod timers;
+pub mod xy_plot;
 // pub mod sub_plot;
 // pub mod sub_status;
 // pub mod sub_text;
@@ -29,13 +56,36 @@ pub use broker_alive::BrokerAlive as BrokerAlive;
 
 #[derive(Debug, Clone)]
 pub enum WidgetMsg {
-    Pub { topic : String, payload : Vec<u8>},
+    Pub { topic : Arc<String>, payload : Arc<Vec<u8>>, captures : Arc<Vec<String>>},
     Tick ,
 }
 
-pub trait PubSubWidget : Send {
+pub trait PubSubWidget {
     fn update(&mut self, event:& WidgetMsg);
     fn draw(&mut self);
+    fn get_config(&self) -> WidgetParams;
+    fn set_config(&mut self, cfg: WidgetParams);
+    /// Called once per frame when the widget received messages since the last one.
+    fn redraw(&mut self);
+    /// Topics the widget needs, by default its src attribute.
+    fn subscriptions(&self) -> Vec<String> {
+        self.get_config().src_topic.into_iter().collect()
+    }
+    /// Period of the Tick messages, by default the msec attribute or once a second.
+    fn tick_period(&self) -> Option<Duration> {
+        Some(self.get_config().msec.map_or(timers::DEFAULT_TICK, |ms| Duration::from_millis(ms.max(1) as u64)))
+    }
+    /// The pubsub connection went up or down.
+    fn on_connection(&mut self, _connected: bool) {}
+    /// The widget was moved or resized, from the layout editor.
+    fn resize(&mut self, _rect: draw::Rect) {}
+    /// State to carry over a hot reload, matched on the widget's config path.
+    fn save_state(&self) -> Option<Vec<u8>> {
+        None
+    }
+    fn restore_state(&mut self, _state: &Vec<u8>) {}
+    /// Deletes the fltk widgets, the widget is removed from the dashboard.
+    fn teardown(&mut self);
 }
 
 
@@ -59,6 +109,7 @@ pub struct Context {
     pub valuator_color: enums::Color,
 
     pub theme: Option<String>,
+    pub palette: BTreeMap<String, enums::Color>,
     pub publish_channel: Option<mpsc::Sender<PubSubEvent>>,
 }
 
@@ -73,11 +124,40 @@ impl Context {
             font_color: enums::Color::Black,
             valuator_color: enums::Color::Green,
             theme: None,
+            palette: BTreeMap::new(),
             publish_channel: None,
         }
     }
+
+    /// The context shared by all widgets, as set up from the Dashboard config.
+    pub fn current() -> Context {
+        CONTEXT.read().unwrap().clone()
+    }
+
+    pub fn set_current(ctx: Context) {
+        *CONTEXT.write().unwrap() = ctx;
+    }
+
+    /// Looks up a palette colour, falling back to `default` when undefined.
+    pub fn color(&self, name: &str, default: enums::Color) -> enums::Color {
+        self.palette.get(name).copied().unwrap_or(default)
+    }
+
+    pub fn bg(&self, cfg: &WidgetParams) -> enums::Color {
+        cfg.bg
+            .as_ref()
+            .and_then(|c| theme::parse_color(c, self))
+            .unwrap_or(self.background_color)
+    }
+
+    pub fn fg(&self, cfg: &WidgetParams) -> enums::Color {
+        cfg.fg
+            .as_ref()
+            .and_then(|c| theme::parse_color(c, self))
+            .unwrap_or(self.font_color)
+    }
 }
 
-static mut CONTEXT: Context =  Context::new() ;
+static CONTEXT: std::sync::RwLock<Context> = std::sync::RwLock::new(Context::new());
 
 
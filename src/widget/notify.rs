@@ -0,0 +1,286 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::process::Command;
+use std::rc::Rc;
+
+use fltk::draw::Rect;
+use fltk::enums::{Color, FrameType};
+use fltk::frame::Frame;
+use fltk::widget::Widget;
+use fltk::{app, prelude::*};
+use log::{info, warn};
+
+use crate::alarm::Severity;
+use crate::config::file_xml::WidgetParams;
+use crate::widget::Context;
+
+const BLINK_PERIOD: f64 = 0.5;
+const BORDER_WIDTH: i32 = 8;
+const DEFAULT_PLAYER: &str = "aplay -q";
+
+/// What blinks while a notification is on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Blink {
+    None,
+    Widget,
+    Window,
+    Both,
+}
+
+impl std::str::FromStr for Blink {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Blink::None),
+            "widget" => Ok(Blink::Widget),
+            "window" => Ok(Blink::Window),
+            "both" => Ok(Blink::Both),
+            _ => Err(format!("Invalid blink {}, expected none, widget, window or both", s)),
+        }
+    }
+}
+
+/// The signals of one severity, from a <Notify> element of the Dashboard.
+/// sound is a WAV file played with player, command runs through sh with
+/// NOTIFY_SEVERITY and NOTIFY_TEXT in its environment.
+#[derive(Debug, Clone)]
+pub struct NotifyAction {
+    pub blink: Blink,
+    pub sound: Option<String>,
+    pub command: Option<String>,
+    pub player: String,
+}
+
+impl NotifyAction {
+    fn default_for(severity: Severity) -> Self {
+        Self {
+            blink: match severity {
+                Severity::Critical => Blink::Both,
+                Severity::Warning => Blink::Widget,
+                Severity::Info => Blink::None,
+            },
+            sound: None,
+            command: None,
+            player: DEFAULT_PLAYER.to_string(),
+        }
+    }
+
+    pub fn from_attrs(attrs: &BTreeMap<String, String>) -> Result<(Severity, Self), String> {
+        let severity: Severity = attrs.get("severity").ok_or("Notify without severity")?.parse()?;
+        let default = Self::default_for(severity);
+        Ok((
+            severity,
+            Self {
+                blink: attrs.get("blink").map_or(Ok(default.blink), |b| b.parse())?,
+                sound: attrs.get("sound").cloned(),
+                command: attrs.get("command").cloned(),
+                player: attrs.get("player").cloned().unwrap_or(default.player),
+            },
+        ))
+    }
+
+    /// The action with the blink, sound and command attributes of a widget.
+    fn for_widget(&self, cfg: &WidgetParams) -> Self {
+        Self {
+            blink: cfg.blink.as_ref().and_then(|b| b.parse().ok()).unwrap_or(self.blink),
+            sound: cfg.sound.clone().or(self.sound.clone()),
+            command: cfg.command.clone().or(self.command.clone()),
+            player: self.player.clone(),
+        }
+    }
+}
+
+/// A notification that is on.
+struct Attention {
+    severity: Severity,
+    window: bool,
+    // the blinking widget and its color before the notification
+    widget: Option<(Widget, Color)>,
+}
+
+/// Draws attention to widgets that time out and to raised alarms : blinks
+/// the widget or a border around the window, plays a sound or runs a
+/// command, as configured per severity with <Notify> and per widget with
+/// the notify (a severity), blink, sound and command attributes. Each
+/// source signals once, until it is cleared.
+pub struct Notifier {
+    actions: BTreeMap<Severity, NotifyAction>,
+    active: BTreeMap<String, Attention>,
+    border: Vec<Frame>,
+    blink_on: bool,
+    ctx: Context,
+}
+
+impl Notifier {
+    pub fn new(ctx: &Context) -> Self {
+        Self {
+            actions: BTreeMap::new(),
+            active: BTreeMap::new(),
+            border: Vec::new(),
+            blink_on: false,
+            ctx: ctx.clone(),
+        }
+    }
+
+    pub fn set_actions(&mut self, actions: Vec<(Severity, NotifyAction)>) {
+        self.actions = actions.into_iter().collect();
+    }
+
+    fn action(&self, severity: Severity) -> NotifyAction {
+        self.actions
+            .get(&severity)
+            .cloned()
+            .unwrap_or_else(|| NotifyAction::default_for(severity))
+    }
+
+    /// Signals the timeout of a widget with the severity of its notify
+    /// attribute, does nothing without one.
+    pub fn notify_widget<W: WidgetExt>(&mut self, cfg: &WidgetParams, widget: Option<&W>, text: &str) {
+        let Some(severity) = cfg.notify.as_ref().and_then(|s| s.parse::<Severity>().ok()) else {
+            return;
+        };
+        let action = self.action(severity).for_widget(cfg);
+        let widget = widget.map(|w| w.as_base_widget());
+        self.start(widget_source(cfg), severity, action, widget, text);
+    }
+
+    pub fn clear_widget(&mut self, cfg: &WidgetParams) {
+        self.clear(&widget_source(cfg));
+    }
+
+    /// Signals a source without widget, such as an alarm.
+    pub fn notify(&mut self, source: &str, severity: Severity, text: &str) {
+        let action = self.action(severity);
+        self.start(source.to_string(), severity, action, None, text);
+    }
+
+    fn start(&mut self, source: String, severity: Severity, action: NotifyAction, widget: Option<Widget>, text: &str) {
+        if self.active.contains_key(&source) {
+            return;
+        }
+        info!("Notify {} {}", severity, text);
+        if let Some(sound) = action.sound.as_ref() {
+            let mut args = action.player.split_whitespace();
+            if let Some(player) = args.next() {
+                let mut command = Command::new(player);
+                command.args(args).arg(sound);
+                spawn(command);
+            }
+        }
+        if let Some(line) = action.command.as_ref() {
+            let mut command = Command::new("sh");
+            command
+                .arg("-c")
+                .arg(line)
+                .env("NOTIFY_SEVERITY", severity.to_string())
+                .env("NOTIFY_TEXT", text);
+            spawn(command);
+        }
+        let blink_widget = matches!(action.blink, Blink::Widget | Blink::Both);
+        self.active.insert(
+            source,
+            Attention {
+                severity,
+                window: matches!(action.blink, Blink::Window | Blink::Both),
+                widget: widget.filter(|_| blink_widget).map(|w| {
+                    let color = w.color();
+                    (w, color)
+                }),
+            },
+        );
+    }
+
+    /// Ends the notification of the source and restores the widget color.
+    pub fn clear(&mut self, source: &str) {
+        let Some(attention) = self.active.remove(source) else {
+            return;
+        };
+        if let Some((mut widget, color)) = attention.widget {
+            if !widget.was_deleted() {
+                widget.set_color(color);
+                widget.redraw();
+            }
+        }
+        if !self.active.values().any(|a| a.window) {
+            for frame in self.border.iter_mut() {
+                frame.hide();
+            }
+        }
+    }
+
+    fn blink(&mut self) {
+        if self.active.is_empty() {
+            return;
+        }
+        self.blink_on = !self.blink_on;
+        let ctx = &self.ctx;
+        for attention in self.active.values_mut() {
+            if let Some((widget, color)) = attention.widget.as_mut() {
+                if widget.was_deleted() {
+                    attention.widget = None;
+                    continue;
+                }
+                let on = severity_color(ctx, attention.severity);
+                widget.set_color(if self.blink_on { on } else { *color });
+                widget.redraw();
+            }
+        }
+        let window = self.active.values().filter(|a| a.window).map(|a| a.severity).max();
+        for frame in self.border.iter_mut() {
+            match window {
+                Some(severity) if self.blink_on => {
+                    frame.set_color(severity_color(ctx, severity));
+                    frame.show();
+                    frame.redraw();
+                }
+                _ => frame.hide(),
+            }
+        }
+    }
+}
+
+/// The color that signals a severity.
+pub fn severity_color(ctx: &Context, severity: Severity) -> Color {
+    match severity {
+        Severity::Critical => ctx.color("alarm", Color::from_u32(0xFF0000)),
+        Severity::Warning => ctx.color("warning", Color::from_u32(0xFFB000)),
+        Severity::Info => ctx.color("ok", Color::from_u32(0x00C000)),
+    }
+}
+
+fn widget_source(cfg: &WidgetParams) -> String {
+    format!("{} {:?}", cfg.name, cfg.path)
+}
+
+fn spawn(mut command: Command) {
+    match command.spawn() {
+        // reaped by a thread so it doesn't linger as a zombie
+        Ok(mut child) => {
+            std::thread::spawn(move || child.wait());
+        }
+        Err(e) => warn!("Notify {:?} failed : {}", command, e),
+    }
+}
+
+/// Adds the window border to the current group and starts the blinking.
+pub fn attach_notifier(notifier: Rc<RefCell<Notifier>>, rect: Rect) {
+    let sides = [
+        (rect.x, rect.y, rect.w, BORDER_WIDTH),
+        (rect.x, rect.y + rect.h - BORDER_WIDTH, rect.w, BORDER_WIDTH),
+        (rect.x, rect.y, BORDER_WIDTH, rect.h),
+        (rect.x + rect.w - BORDER_WIDTH, rect.y, BORDER_WIDTH, rect.h),
+    ];
+    notifier.borrow_mut().border = sides
+        .iter()
+        .map(|(x, y, w, h)| {
+            let mut frame = Frame::new(*x, *y, *w, *h, None);
+            frame.set_frame(FrameType::FlatBox);
+            frame.hide();
+            frame
+        })
+        .collect();
+    app::add_timeout3(BLINK_PERIOD, move |handle| {
+        notifier.borrow_mut().blink();
+        app::repeat_timeout3(BLINK_PERIOD, handle);
+    });
+}
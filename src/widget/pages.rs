@@ -0,0 +1,157 @@
+use fltk::enums::{Event, Key};
+use fltk::group::{Group, Tabs, Wizard};
+use fltk::{app, prelude::*};
+use log::{info, warn};
+
+use crate::config::file_xml::WidgetParams;
+
+// Tab pages get a clickable tab header, Page pages are only reachable by
+// keyboard shortcut or by the auto-rotate timer of the Dashboard.
+#[derive(Clone)]
+enum PagesGroup {
+    Tabs(Tabs),
+    Wizard(Wizard),
+}
+
+#[derive(Clone)]
+pub struct Pages {
+    group: PagesGroup,
+    pages: Vec<Group>,
+    keys: Vec<Option<Key>>,
+}
+
+impl Pages {
+    /// Creates the page container and one empty Group per page.
+    /// The children of each page are created by `fill`, called with the
+    /// page's group opened so that new fltk widgets land inside it.
+    pub fn new<F>(rect: fltk::draw::Rect, pages_params: &[WidgetParams], mut fill: F) -> Result<Self, String>
+    where
+        F: FnMut(&WidgetParams) -> Result<(), String>,
+    {
+        let with_tabs = pages_params.iter().any(|p| p.name == "Tab");
+        let group = if with_tabs {
+            PagesGroup::Tabs(Tabs::new(rect.x, rect.y, rect.w, rect.h, None))
+        } else {
+            PagesGroup::Wizard(Wizard::new(rect.x, rect.y, rect.w, rect.h, None))
+        };
+        let mut pages = Vec::new();
+        let mut keys = Vec::new();
+        for (idx, page_params) in pages_params.iter().enumerate() {
+            let r = page_params.rect;
+            let mut page = Group::new(r.x, r.y, r.w, r.h, None);
+            page.set_label(page_params.label.as_deref().unwrap_or(&page_params.name));
+            let filled = fill(page_params);
+            page.end();
+            if let Err(e) = filled {
+                // leave the group that was current before the pages
+                end_group(&group);
+                return Err(e);
+            }
+            // F1 to F12 by default, later pages only by their key attribute
+            let default_key = (idx < 12).then(|| Key::from_i32(Key::F1.bits() + idx as i32));
+            keys.push(page_params.key.as_deref().map_or(default_key, parse_key));
+            pages.push(page);
+        }
+        end_group(&group);
+        let mut me = Self { group, pages, keys };
+        me.select(0);
+        Ok(me)
+    }
+
+    pub fn count(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn current(&self) -> usize {
+        let current = match &self.group {
+            PagesGroup::Tabs(tabs) => tabs.value().map(|w| w.as_widget_ptr()),
+            PagesGroup::Wizard(wizard) => wizard.current_widget().map(|w| w.as_widget_ptr()),
+        };
+        self.pages
+            .iter()
+            .position(|p| Some(p.as_widget_ptr()) == current)
+            .unwrap_or(0)
+    }
+
+    pub fn select(&mut self, idx: usize) {
+        if let Some(page) = self.pages.get(idx) {
+            match &mut self.group {
+                PagesGroup::Tabs(tabs) => {
+                    let _ = tabs.set_value(page);
+                    tabs.redraw();
+                }
+                PagesGroup::Wizard(wizard) => {
+                    wizard.set_current_widget(page);
+                    wizard.redraw();
+                }
+            }
+        }
+    }
+
+    pub fn next(&mut self) {
+        if self.count() > 0 {
+            let idx = (self.current() + 1) % self.count();
+            self.select(idx);
+        }
+    }
+
+    /// Selects the page bound to the key of the current shortcut event.
+    pub fn handle_shortcut(&mut self) -> bool {
+        let key = app::event_key();
+        match self.keys.iter().position(|k| *k == Some(key)) {
+            Some(idx) => {
+                self.select(idx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cycles through the pages every `secs` seconds. The handle removes
+    /// the timer when the pages are rebuilt.
+    pub fn auto_rotate(&self, secs: i32) -> Option<app::TimeoutHandle> {
+        if secs <= 0 {
+            return None;
+        }
+        info!("Rotating pages every {} sec", secs);
+        let mut pages = self.clone();
+        Some(app::add_timeout3(secs as f64, move |handle| {
+            pages.next();
+            app::repeat_timeout3(secs as f64, handle);
+        }))
+    }
+}
+
+fn end_group(group: &PagesGroup) {
+    match group {
+        PagesGroup::Tabs(tabs) => tabs.end(),
+        PagesGroup::Wizard(wizard) => wizard.end(),
+    }
+}
+
+/// Binds the pages' shortcuts on the window.
+pub fn handle_page_keys<W: WidgetBase>(win: &mut W, pages: &Pages) {
+    let mut pages = pages.clone();
+    win.handle(move |_, ev| match ev {
+        Event::Shortcut => pages.handle_shortcut(),
+        _ => false,
+    });
+}
+
+fn parse_key(key: &str) -> Option<Key> {
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(Key::from_char(c.to_ascii_lowercase())),
+        (Some('F'), Some(_)) => match key[1..].parse::<i32>() {
+            Ok(n) if (1..=12).contains(&n) => Some(Key::from_i32(Key::F1.bits() + n - 1)),
+            _ => {
+                warn!("Invalid page key {}", key);
+                None
+            }
+        },
+        _ => {
+            warn!("Invalid page key {}", key);
+            None
+        }
+    }
+}
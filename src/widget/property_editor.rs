@@ -0,0 +1,167 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use fltk::button::Button;
+use fltk::enums::Align;
+use fltk::frame::Frame;
+use fltk::group::{Flex, Pack, Scroll, ScrollType};
+use fltk::input::Input;
+use fltk::menu::Choice;
+use fltk::{app, dialog, prelude::*, window};
+use log::{error, info};
+
+use crate::config::file_xml::EDITABLE_ATTRS;
+use crate::widget::edit::Editor;
+
+const ROW_HEIGHT: i32 = 25;
+const WIDTH: i32 = 400;
+// rows shown before the form scrolls
+const MAX_ROWS: i32 = 12;
+// offered for every widget, even when not set
+const COMMON_ATTRS: [&str; 8] = ["label", "src", "dst", "bg", "fg", "font", "text_size", "frame"];
+
+type Inputs = Rc<RefCell<Vec<(&'static str, Input)>>>;
+
+/// Opens a form with the attributes set on the widget and the common ones,
+/// others can be added from the list below it. Apply hands the changes to
+/// the running widget, Save also writes the config file. An emptied field
+/// removes the attribute.
+pub fn open_property_editor(editor: Rc<RefCell<Editor>>, path: &[usize]) {
+    let Some(cfg) = editor.borrow().config(path) else {
+        return;
+    };
+    let path = path.to_vec();
+    let title = format!("{} {}", cfg.name, cfg.label.clone().unwrap_or_default());
+    let form_height = MAX_ROWS * ROW_HEIGHT;
+    let height = form_height + 2 * ROW_HEIGHT + 40;
+    let mut win = window::Window::new(app::event_x_root(), app::event_y_root(), WIDTH, height, None);
+    win.set_label(&title);
+
+    let mut scroll = Scroll::new(10, 10, WIDTH - 20, form_height, None);
+    scroll.set_type(ScrollType::Vertical);
+    // leaves room for the scrollbar
+    let mut pack = Pack::new(10, 10, WIDTH - 40, 0, None);
+    pack.end();
+    scroll.end();
+
+    let inputs: Inputs = Rc::new(RefCell::new(Vec::new()));
+    for attr in EDITABLE_ATTRS {
+        let value = cfg.get_attr(attr);
+        if value.is_some() || COMMON_ATTRS.contains(&attr) {
+            add_row(&mut pack, &inputs, attr, value);
+        }
+    }
+
+    let mut extra = Flex::new(10, 20 + form_height, WIDTH - 20, ROW_HEIGHT, None).row();
+    let mut choice = Choice::default();
+    let mut add = Button::default().with_label("Add");
+    extra.fixed(&add, 80);
+    extra.end();
+    let refill_choice = {
+        let inputs = inputs.clone();
+        move |choice: &mut Choice| {
+            choice.clear();
+            let shown = inputs.borrow();
+            for attr in EDITABLE_ATTRS.iter().filter(|a| !shown.iter().any(|(s, _)| s == *a)) {
+                choice.add_choice(attr);
+            }
+            choice.set_value(0);
+        }
+    };
+    refill_choice(&mut choice);
+    add.set_callback({
+        let inputs = inputs.clone();
+        let mut pack = pack.clone();
+        let mut scroll = scroll.clone();
+        let mut choice = choice.clone();
+        move |_| {
+            let Some(attr) = choice
+                .choice()
+                .and_then(|c| EDITABLE_ATTRS.iter().copied().find(|a| *a == c))
+            else {
+                return;
+            };
+            add_row(&mut pack, &inputs, attr, None);
+            refill_choice(&mut choice);
+            scroll.redraw();
+        }
+    });
+
+    let mut buttons = Flex::new(10, 30 + form_height + ROW_HEIGHT, WIDTH - 20, ROW_HEIGHT, None).row();
+    let mut apply = Button::default().with_label("Apply");
+    let mut save = Button::default().with_label("Save");
+    let mut close = Button::default().with_label("Close");
+    buttons.end();
+    win.end();
+    win.make_modal(true);
+    win.show();
+
+    // collects the form into a new config, reporting the first invalid field
+    let apply_form = {
+        let editor = editor.clone();
+        move || -> bool {
+            let Some(mut cfg) = editor.borrow().config(&path) else {
+                dialog::alert_default("The widget is gone after a reload");
+                return false;
+            };
+            for (attr, input) in inputs.borrow().iter() {
+                let value = input.value();
+                let result = if value.is_empty() {
+                    cfg.unset_attr(attr)
+                } else {
+                    cfg.set_attr(attr, &value)
+                };
+                if let Err(e) = result {
+                    dialog::alert_default(&e);
+                    return false;
+                }
+            }
+            info!("Applying properties of {} {:?}", cfg.name, cfg.path);
+            editor.borrow_mut().set_config(&path, cfg)
+        }
+    };
+    let apply_form = Rc::new(apply_form);
+
+    apply.set_callback({
+        let apply_form = apply_form.clone();
+        move |_| {
+            apply_form();
+        }
+    });
+    save.set_callback({
+        let apply_form = apply_form.clone();
+        move |_| {
+            if apply_form() {
+                if let Err(e) = editor.borrow().save() {
+                    error!("Saving config failed : {}", e);
+                    dialog::alert_default(&e);
+                }
+            }
+        }
+    });
+    // the close button of the title bar ends up here as well
+    win.set_callback(|win| {
+        win.hide();
+        app::delete_widget(win.clone());
+    });
+    close.set_callback({
+        let mut win = win.clone();
+        move |_| win.do_callback()
+    });
+}
+
+fn add_row(pack: &mut Pack, inputs: &Inputs, attr: &'static str, value: Option<String>) {
+    let mut row = Flex::new(0, 0, pack.w(), ROW_HEIGHT, None).row();
+    row.set_pad(20);
+    Frame::default()
+        .with_label(attr)
+        .with_align(Align::Inside | Align::Right);
+    let mut input = Input::default();
+    value.map(|v| input.set_value(&v));
+    row.fixed(&input, 260);
+    row.end();
+    // moved over without begin(), which would leave the pack current
+    pack.add(&row);
+    pack.resize(pack.x(), pack.y(), pack.w(), pack.children() * ROW_HEIGHT);
+    inputs.borrow_mut().push((attr, input));
+}
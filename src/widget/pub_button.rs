@@ -9,7 +9,7 @@ use serde_yaml::Value;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use std::time::SystemTime;
 
 use crate::config::file_xml::WidgetParams;
@@ -17,6 +17,7 @@ use crate::limero::{SinkRef, SinkTrait};
 use crate::pubsub::{payload_as_f64, payload_decode, payload_encode, PubSubCmd, PubSubEvent};
 use crate::widget::hms;
 use crate::widget::Context;
+use crate::widget::theme::apply_style;
 use tokio::sync::mpsc;
 
 use evalexpr::Value as V;
@@ -32,6 +33,7 @@ pub struct PubButton {
     cfg: WidgetParams,
     ctx: Context,
     frame : Option<fltk::frame::Frame>,
+    button : Option<Button>,
     pubsub_cmd : SinkRef<PubSubCmd>,
 }
 
@@ -43,11 +45,55 @@ impl PubButton
             last_update: std::time::UNIX_EPOCH,
             eval_expr: None,
             cfg: cfg.clone(),
-            ctx: Context::new(),
+            ctx: Context::current(),
             frame: None,
+            button: None,
             pubsub_cmd,
         }
     }
+
+    /// Publishes `on` when pressed and `off` when released on dst. Without
+    /// dst, or without the value, nothing is published.
+    fn publish_handler<W>(&self) -> impl FnMut(&mut W, Event) -> bool {
+        let pubsub_cmd = self.pubsub_cmd.clone();
+        let dst_topic = self.cfg.dst_topic.clone();
+        let on_value = self.cfg.on.clone();
+        let off_value = self.cfg.off.clone();
+        let name = self.cfg.label.clone().unwrap_or_else(|| self.cfg.name.clone());
+        move |_w, ev| {
+            let value = match ev {
+                enums::Event::Push => on_value.as_ref(),
+                enums::Event::Released => off_value.as_ref(),
+                _ => return false,
+            };
+            let Some(value) = value else {
+                return false;
+            };
+            if app::event_mouse_button() != app::MouseButton::Left {
+                return false;
+            }
+            let Some(topic) = dst_topic.as_ref() else {
+                warn!("Button {} has no dst, {} not published", name, value);
+                return true;
+            };
+            pubsub_cmd.push(PubSubCmd::Publish {
+                topic: topic.clone(),
+                payload: to_cbor(value),
+            });
+            true
+        }
+    }
+
+    fn style_button(&self, button: &mut Button) {
+        apply_style(button, &self.cfg, &self.ctx);
+        if self.cfg.bg.is_none() {
+            button.set_color(self.ctx.color("button", Color::Blue));
+        }
+        if self.cfg.fg.is_none() {
+            button.set_label_color(self.ctx.color("button_text", Color::White));
+        }
+        self.cfg.label.as_ref().map(|s| button.set_label(s.as_str()));
+    }
 }
 
 impl PubSubWidget for PubButton {
@@ -61,96 +107,66 @@ impl PubSubWidget for PubButton {
         );
 //        frame.set_frame(FrameType::BorderBox);
         frame.set_align(Align::Center);
-        let label = self.cfg.label.as_ref().unwrap().clone();
     //    self.cfg.label.as_ref().map(|s| frame.set_label(s.as_str()));
         let mut button = Button::default().with_size(self.cfg.rect.w-10, self.cfg.rect.h-5).center_of(&frame);
-        button.set_color(Color::Blue);
-        button.set_label_color(Color::White);
-        self.cfg.label.as_ref().map(|s| button.set_label(s.as_str()));
-        button.handle( {
-            let pubsub_cmd = self.pubsub_cmd.clone();
-            let dst_topic = self.cfg.dst_topic.as_ref().unwrap().clone();
-            let on_value = self.cfg.on.clone();
-            let off_value = self.cfg.off.clone();
-            move |w, ev| match ev {
-                enums::Event::Push => {
-                    if app::event_mouse_button() == app::MouseButton::Left && on_value.is_some() {
-                        pubsub_cmd.push(PubSubCmd::Publish {
-                            topic: dst_topic.clone(),
-                            payload: to_cbor(on_value.as_ref().unwrap()),
-                        });
-                        true
-                    } else {
-                        false
-                    }
-                }
-                enums::Event::Released => {
-                    if app::event_mouse_button() == app::MouseButton::Left && off_value.is_some() {
-                        pubsub_cmd.push(PubSubCmd::Publish {
-                            topic: dst_topic.clone(),
-                            payload: to_cbor(off_value.as_ref().unwrap()),
-                        });
-                        true
-                    } else {
-                        false
-                    }
-                }
-                _ev => false,
-            }
-        });
+        self.style_button(&mut button);
+        button.handle(self.publish_handler());
+        frame.handle(self.publish_handler());
+        self.button = Some(button);
+        self.frame = Some(frame);
 
+    }
 
-        let pubsub_cmd = self.pubsub_cmd.clone();
-        let dst_topic = self.cfg.dst_topic.as_ref().unwrap().clone();
-        let on_value = self.cfg.on.clone();
-        let off_value = self.cfg.off.clone();
+    fn get_config(&self) -> WidgetParams {
+        self.cfg.clone()
+    }
 
-        frame.handle({
-            move |w, ev| match ev {
-                enums::Event::Push => {
-                    if app::event_mouse_button() == app::MouseButton::Left && on_value.is_some() {
-                        pubsub_cmd.push(PubSubCmd::Publish {
-                            topic: dst_topic.clone(),
-                            payload: to_cbor(on_value.as_ref().unwrap()),
-                        });
-                        true
-                    } else {
-                        false
-                    }
-                }
-                enums::Event::Released => {
-                    if app::event_mouse_button() == app::MouseButton::Left && off_value.is_some() {
-                        pubsub_cmd.push(PubSubCmd::Publish {
-                            topic: dst_topic.clone(),
-                            payload: to_cbor(off_value.as_ref().unwrap()),
-                        });
-                        true
-                    } else {
-                        false
-                    }
-                }
-                _ev => false,
-            }
-        });
-        self.frame = Some(frame);
+    fn set_config(&mut self, cfg: WidgetParams) {
+        self.cfg = cfg;
+        let handler = self.publish_handler();
+        let frame_handler = self.publish_handler();
+        if let Some(mut button) = self.button.clone() {
+            self.style_button(&mut button);
+            button.handle(handler);
+            button.redraw();
+        }
+        self.frame.as_mut().map(|f| f.handle(frame_handler));
+    }
+
+    fn redraw(&mut self) {
+        self.frame.as_mut().map(|f| f.redraw());
+    }
+
+    fn tick_period(&self) -> Option<Duration> {
+        None
+    }
+
+    fn resize(&mut self, rect: Rect) {
+        self.cfg.rect = rect;
+        self.frame.as_mut().map(|f| f.resize(rect.x, rect.y, rect.w, rect.h));
+        // keep the button centered in the frame, as in draw
+        self.button
+            .as_mut()
+            .map(|b| b.resize(rect.x + 5, rect.y + 2, rect.w - 10, rect.h - 5));
+    }
 
+    fn teardown(&mut self) {
+        self.button.take().map(app::delete_widget);
+        self.frame.take().map(app::delete_widget);
     }
 
     fn update(&mut self, event: & WidgetMsg) {
         match event {
-            WidgetMsg::Pub { topic, payload } => {
-                let topic_cfg = self.cfg.src_topic.as_ref().clone();
-                if *topic == *topic_cfg.unwrap() {
-                    info!("SubLabel: {:?}", payload);
-                    let _ = payload_as_f64(&payload).and_then  (|v| {
-                        self.value = v;
-                        let binding = " ?? ".to_string();
-                        let suffix = self.cfg.suffix.as_ref().unwrap_or(&binding);
-                        let line = format!("{:.2} {}", self.value,suffix);
-                        self.frame.as_mut().map(|f| f.set_label(&line));
-                        Ok(())
-                    });
-                }
+            WidgetMsg::Pub { topic, payload, .. } => {
+                info!("SubLabel: {:?}", payload);
+                let _ = payload_as_f64(&payload).and_then  (|v| {
+                    self.value = v;
+                    let binding = " ?? ".to_string();
+                    let suffix = self.cfg.suffix.as_ref().unwrap_or(&binding);
+                    let line = format!("{:.2} {}", self.value,suffix);
+                    self.frame.as_mut().map(|f| f.set_label(&line));
+                    Ok(())
+                });
             }
             _ => {}
         }
@@ -158,7 +174,8 @@ impl PubSubWidget for PubButton {
 }
 
 
-fn to_cbor(value: &str) -> Vec<u8> {
+/// Encodes a config value as the number, bool or string it looks like.
+pub fn to_cbor(value: &str) -> Vec<u8> {
     if is_integer(value) {
         let v = value.parse::<i64>().unwrap();
         payload_encode(v)
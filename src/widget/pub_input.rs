@@ -0,0 +1,182 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use fltk::draw::Rect;
+use fltk::enums::{Align, CallbackTrigger, Color, FrameType};
+use fltk::frame::Frame;
+use fltk::misc::InputChoice;
+use fltk::{app, prelude::*};
+use log::{info, warn};
+
+use crate::config::file_xml::WidgetParams;
+use crate::limero::{SinkRef, SinkTrait};
+use crate::pubsub::{json_encode, payload_decode, payload_encode, PubSubCmd};
+use crate::widget::pub_button::to_cbor;
+use crate::widget::theme::apply_style;
+use crate::widget::Context;
+
+use super::{PubSubWidget, WidgetMsg};
+
+const DEFAULT_HISTORY: usize = 10;
+
+/// Text field that publishes the typed value to dst on Enter. The type
+/// attribute (int, float, bool, string or json) validates the text and
+/// chooses the payload encoding, without it the value is encoded as what
+/// it looks like. Published values are kept in the dropdown.
+pub struct PubInput {
+    cfg: WidgetParams,
+    ctx: Context,
+    frame: Option<Frame>,
+    input: Option<InputChoice>,
+    history: Rc<RefCell<Vec<String>>>,
+    pubsub_cmd: SinkRef<PubSubCmd>,
+}
+
+impl PubInput {
+    pub fn new(cfg: &WidgetParams, pubsub_cmd: SinkRef<PubSubCmd>) -> Self {
+        Self {
+            cfg: cfg.clone(),
+            ctx: Context::current(),
+            frame: None,
+            input: None,
+            history: Rc::new(RefCell::new(Vec::new())),
+            pubsub_cmd,
+        }
+    }
+
+    fn set_callback(&mut self) {
+        let cfg = self.cfg.clone();
+        let ctx = self.ctx.clone();
+        let history = self.history.clone();
+        let pubsub_cmd = self.pubsub_cmd.clone();
+        let Some(choice) = self.input.as_mut() else {
+            return;
+        };
+        let mut menu = choice.clone();
+        let mut input = choice.input();
+        input.set_trigger(CallbackTrigger::EnterKeyAlways);
+        input.set_callback(move |input| {
+            let text = input.value();
+            match encode_input(&text, cfg.value_type.as_deref()) {
+                Ok(payload) => {
+                    input.set_color(ctx.bg(&cfg));
+                    input.set_tooltip("");
+                    if let Some(dst) = cfg.dst_topic.as_ref() {
+                        info!("Input {} = {}", dst, text);
+                        pubsub_cmd.push(PubSubCmd::Publish {
+                            topic: dst.clone(),
+                            payload,
+                        });
+                    }
+                    let mut history = history.borrow_mut();
+                    remember(&mut history, text, cfg.history.unwrap_or(DEFAULT_HISTORY));
+                    fill_menu(&mut menu, &history);
+                }
+                Err(e) => {
+                    warn!("Input {:?} rejected : {}", cfg.dst_topic, e);
+                    input.set_color(ctx.color("alarm", Color::from_u32(0xFF0000)));
+                    input.set_tooltip(&e);
+                }
+            }
+            input.redraw();
+        });
+    }
+}
+
+/// Validates the text for the configured type and encodes it.
+fn encode_input(text: &str, value_type: Option<&str>) -> Result<Vec<u8>, String> {
+    let invalid = |kind: &str| format!("'{}' is not a valid {}", text, kind);
+    match value_type {
+        None => Ok(to_cbor(text)),
+        Some("int") => text.trim().parse::<i64>().map(payload_encode).map_err(|_| invalid("int")),
+        Some("float") => text.trim().parse::<f64>().map(payload_encode).map_err(|_| invalid("float")),
+        Some("bool") => text.trim().parse::<bool>().map(payload_encode).map_err(|_| invalid("bool")),
+        Some("string") => Ok(payload_encode(text)),
+        Some("json") => json_encode(text).map_err(|e| format!("Invalid json : {}", e)),
+        Some(other) => Err(format!("Unknown input type {}", other)),
+    }
+}
+
+/// Puts the value on top of the history, without duplicates.
+fn remember(history: &mut Vec<String>, value: String, size: usize) {
+    history.retain(|v| *v != value);
+    history.insert(0, value);
+    history.truncate(size);
+}
+
+fn fill_menu(choice: &mut InputChoice, history: &[String]) {
+    choice.clear();
+    for value in history {
+        // a slash would make a submenu
+        choice.add(&value.replace('/', "\\/"));
+    }
+}
+
+impl PubSubWidget for PubInput {
+    fn draw(&mut self) {
+        let r = self.cfg.rect;
+        // the label takes the left third, when there is one
+        let label_w = if self.cfg.label.is_some() { r.w / 3 } else { 0 };
+        let mut frame = Frame::new(r.x, r.y, label_w, r.h, None);
+        frame.set_frame(FrameType::NoBox);
+        frame.set_align(Align::Inside | Align::Right);
+        apply_style(&mut frame, &self.cfg, &self.ctx);
+        self.cfg.label.as_ref().map(|s| frame.set_label(s.as_str()));
+        let mut choice = InputChoice::new(r.x + label_w, r.y, r.w - label_w, r.h, None);
+        self.cfg.text_size.map(|size| choice.set_text_size(size));
+        fill_menu(&mut choice, &self.history.borrow());
+        self.frame = Some(frame);
+        self.input = Some(choice);
+        self.set_callback();
+    }
+
+    fn get_config(&self) -> WidgetParams {
+        self.cfg.clone()
+    }
+
+    fn set_config(&mut self, cfg: WidgetParams) {
+        self.cfg = cfg;
+        if let Some(frame) = self.frame.as_mut() {
+            apply_style(frame, &self.cfg, &self.ctx);
+            frame.set_label(self.cfg.label.as_deref().unwrap_or(""));
+        }
+        self.set_callback();
+    }
+
+    fn redraw(&mut self) {
+        self.frame.as_mut().map(|f| f.redraw());
+        self.input.as_mut().map(|i| i.redraw());
+    }
+
+    fn tick_period(&self) -> Option<Duration> {
+        None
+    }
+
+    fn resize(&mut self, rect: Rect) {
+        self.cfg.rect = rect;
+        let label_w = if self.cfg.label.is_some() { rect.w / 3 } else { 0 };
+        self.frame.as_mut().map(|f| f.resize(rect.x, rect.y, label_w, rect.h));
+        self.input
+            .as_mut()
+            .map(|i| i.resize(rect.x + label_w, rect.y, rect.w - label_w, rect.h));
+    }
+
+    fn save_state(&self) -> Option<Vec<u8>> {
+        Some(payload_encode(self.history.borrow().clone()))
+    }
+
+    fn restore_state(&mut self, state: &Vec<u8>) {
+        if let Ok(history) = payload_decode::<Vec<String>>(state) {
+            self.input.as_mut().map(|i| fill_menu(i, &history));
+            *self.history.borrow_mut() = history;
+        }
+    }
+
+    fn teardown(&mut self) {
+        self.input.take().map(app::delete_widget);
+        self.frame.take().map(app::delete_widget);
+    }
+
+    fn update(&mut self, _event: &WidgetMsg) {}
+}
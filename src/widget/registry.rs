@@ -0,0 +1,150 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::time::SystemTime;
+
+use log::info;
+
+use crate::alarm::AlarmManager;
+use crate::config::file_xml::WidgetParams;
+use crate::limero::SinkRef;
+use crate::pubsub::PubSubCmd;
+use crate::widget::alarm_panel::AlarmPanel;
+use crate::widget::bar_chart::BarChart;
+use crate::widget::compass::Compass;
+use crate::widget::console::Console;
+use crate::widget::led_bar::LedBar;
+use crate::widget::macro_button::MacroButton;
+use crate::widget::notify::Notifier;
+use crate::widget::pub_input::PubInput;
+use crate::widget::setpoint::{SetpointDial, SetpointSlider};
+use crate::widget::seven_segment::SevenSegment;
+use crate::widget::stats::Stats;
+use crate::widget::sub_image::SubImage;
+use crate::widget::switch::Switch;
+use crate::widget::xy_plot::XyPlot;
+use crate::widget::{BrokerAlive, Context, PubButton, PubSubWidget, SubGauge, SubLabel};
+
+/// What a widget may need from the application when it is built.
+#[derive(Clone)]
+pub struct Services {
+    pub pubsub_cmd: SinkRef<PubSubCmd>,
+    pub ctx: Context,
+    /// Wall clock for timestamps, replaceable in tests.
+    pub clock: fn() -> SystemTime,
+    pub alarms: Rc<RefCell<AlarmManager>>,
+    pub notifier: Rc<RefCell<Notifier>>,
+}
+
+impl Services {
+    pub fn new(pubsub_cmd: SinkRef<PubSubCmd>, ctx: Context) -> Self {
+        let notifier = Rc::new(RefCell::new(Notifier::new(&ctx)));
+        let alarms = Rc::new(RefCell::new(AlarmManager::new()));
+        alarms.borrow_mut().set_notifier(notifier.clone());
+        Self {
+            pubsub_cmd,
+            ctx,
+            clock: SystemTime::now,
+            alarms,
+            notifier,
+        }
+    }
+}
+
+pub type WidgetConstructor = Box<dyn Fn(&WidgetParams, &Services) -> Box<dyn PubSubWidget>>;
+
+/// Maps the tag names of the config to widget constructors. Widget types
+/// from outside `with_defaults` are added with `register` before the
+/// dashboard is built.
+pub struct WidgetRegistry {
+    constructors: BTreeMap<String, WidgetConstructor>,
+}
+
+impl WidgetRegistry {
+    pub fn new() -> Self {
+        Self {
+            constructors: BTreeMap::new(),
+        }
+    }
+
+    /// A registry with all widgets of this crate.
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        registry.register("Label", |cfg, services| {
+            Box::new(SubLabel::new(cfg, services.notifier.clone()))
+        });
+        registry.register("Gauge", |cfg, services| Box::new(SubGauge::new(cfg, &services.ctx)));
+        registry.register("Table", |cfg, services| {
+            Box::new(SubLabel::new(cfg, services.notifier.clone()))
+        });
+        registry.register("Progress", |cfg, services| {
+            Box::new(SubLabel::new(cfg, services.notifier.clone()))
+        });
+        registry.register("Plot", |cfg, services| {
+            Box::new(SubLabel::new(cfg, services.notifier.clone()))
+        });
+        registry.register("Compass", |cfg, _| Box::new(Compass::new(cfg)));
+        registry.register("SevenSegment", |cfg, _| Box::new(SevenSegment::new(cfg)));
+        registry.register("LedBar", |cfg, _| Box::new(LedBar::new(cfg)));
+        registry.register("Image", |cfg, _| Box::new(SubImage::new(cfg)));
+        registry.register("XyPlot", |cfg, _| Box::new(XyPlot::new(cfg)));
+        registry.register("Stats", |cfg, _| Box::new(Stats::new(cfg)));
+        registry.register("BarChart", |cfg, _| Box::new(BarChart::new(cfg)));
+        registry.register("Console", |cfg, services| Box::new(Console::new(cfg, services.clock)));
+        registry.register("Alarm", |cfg, services| {
+            Box::new(AlarmPanel::new(cfg, services.pubsub_cmd.clone(), services.alarms.clone()))
+        });
+        registry.register("BrokerAlive", |cfg, services| {
+            Box::new(BrokerAlive::new(cfg, services.pubsub_cmd.clone(), services.notifier.clone()))
+        });
+        registry.register("MacroButton", |cfg, services| {
+            Box::new(MacroButton::new(cfg, services.pubsub_cmd.clone()))
+        });
+        registry.register("Button", |cfg, services| {
+            Box::new(PubButton::new(cfg, services.pubsub_cmd.clone()))
+        });
+        registry.register("Input", |cfg, services| {
+            Box::new(PubInput::new(cfg, services.pubsub_cmd.clone()))
+        });
+        registry.register("Switch", |cfg, services| {
+            Box::new(Switch::new(cfg, services.pubsub_cmd.clone()))
+        });
+        registry.register("Slider", |cfg, services| {
+            Box::new(SetpointSlider::new(cfg, services.pubsub_cmd.clone()))
+        });
+        registry.register("Dial", |cfg, services| {
+            Box::new(SetpointDial::new(cfg, services.pubsub_cmd.clone()))
+        });
+        registry
+    }
+
+    pub fn register<F>(&mut self, name: &str, constructor: F)
+    where
+        F: Fn(&WidgetParams, &Services) -> Box<dyn PubSubWidget> + 'static,
+    {
+        self.constructors
+            .insert(name.to_string(), Box::new(constructor));
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.constructors.keys().map(|k| k.as_str()).collect()
+    }
+
+    /// Builds and draws the widget for a config tag.
+    pub fn create(
+        &self,
+        cfg: &WidgetParams,
+        services: &Services,
+    ) -> Result<Box<dyn PubSubWidget>, String> {
+        let constructor = self.constructors.get(&cfg.name).ok_or(format!(
+            "Unknown widget type {} at {:?}, known types are {}",
+            cfg.name,
+            cfg.path,
+            self.names().join(", ")
+        ))?;
+        info!("Loading widget {}", cfg.name);
+        let mut widget = constructor(cfg, services);
+        widget.draw();
+        Ok(widget)
+    }
+}
@@ -0,0 +1,95 @@
+use std::time::{Duration, Instant};
+
+use fltk::enums::{Align, Color, FrameType};
+use fltk::frame::Frame;
+use fltk::{app, prelude::*};
+use log::{info, warn};
+use tokio::sync::mpsc::Receiver;
+
+use crate::widget::dispatcher::{Dispatcher, UiMsg};
+
+pub const DEFAULT_FPS: i32 = 25;
+const STATS_WIDTH: i32 = 160;
+const STATS_HEIGHT: i32 = 20;
+
+/// Runs the frames on the UI thread : every 1/fps second the queued messages
+/// are applied, the widget timers are checked and only the widgets that
+/// changed are redrawn.
+pub struct RenderScheduler {
+    dispatcher: Dispatcher,
+    receiver: Receiver<UiMsg>,
+    fps: f64,
+    stats: Option<Frame>,
+    frames: u32,
+    messages: usize,
+    last_stats: Instant,
+}
+
+impl RenderScheduler {
+    pub fn new(dispatcher: Dispatcher, receiver: Receiver<UiMsg>, fps: i32) -> Self {
+        Self {
+            dispatcher,
+            receiver,
+            fps: fps.clamp(1, 100) as f64,
+            stats: None,
+            frames: 0,
+            messages: 0,
+            last_stats: Instant::now(),
+        }
+    }
+
+    /// Adds the frames and messages per second overlay in the top right
+    /// corner of the window, call while the window is still open.
+    pub fn with_stats(mut self, screen_width: i32) -> Self {
+        let mut frame = Frame::new(screen_width - STATS_WIDTH, 0, STATS_WIDTH, STATS_HEIGHT, None);
+        frame.set_frame(FrameType::FlatBox);
+        frame.set_color(Color::from_u32(0x202020));
+        frame.set_label_color(Color::from_u32(0x00FF00));
+        frame.set_label_size(12);
+        frame.set_align(Align::Inside | Align::Right);
+        self.stats = Some(frame);
+        self
+    }
+
+    pub fn start(mut self) {
+        info!("Rendering at max {} fps", self.fps);
+        let period = 1.0 / self.fps;
+        if let Some(tick) = self.dispatcher.shortest_tick() {
+            if tick.as_secs_f64() < period {
+                warn!(
+                    "Widget timers of {} msec tick once per frame, every {:.0} msec at {} fps",
+                    tick.as_millis(),
+                    period * 1000.0,
+                    self.fps
+                );
+            }
+        }
+        app::add_timeout3(period, move |handle| {
+            self.frame();
+            app::repeat_timeout3(period, handle);
+        });
+    }
+
+    fn frame(&mut self) {
+        self.messages += self.dispatcher.drain(&mut self.receiver);
+        self.dispatcher.fire_timers();
+        if self.dispatcher.redraw_dirty() > 0 {
+            self.frames += 1;
+        }
+        let elapsed = self.last_stats.elapsed();
+        if elapsed >= Duration::from_secs(1) {
+            if let Some(stats) = self.stats.as_mut() {
+                let secs = elapsed.as_secs_f64();
+                stats.set_label(&format!(
+                    "{:.0} fps {:.0} msg/s ",
+                    self.frames as f64 / secs,
+                    self.messages as f64 / secs
+                ));
+                stats.redraw();
+            }
+            self.frames = 0;
+            self.messages = 0;
+            self.last_stats = Instant::now();
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use fltk::draw::Rect;
+use fltk::enums::{Align, Color, Event};
+use fltk::valuator::{Dial, Slider, SliderType};
+use fltk::{app, prelude::*};
+
+use crate::config::file_xml::WidgetParams;
+use crate::limero::{SinkRef, SinkTrait};
+use crate::pubsub::{payload_as_f64, payload_encode, PubSubCmd};
+use crate::widget::theme::apply_style;
+use crate::widget::Context;
+
+use super::{PubSubWidget, WidgetMsg};
+
+// while dragging, values are published at most this often
+const PUBLISH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The fltk valuators a setpoint can be shown as.
+pub trait SetpointValuator: ValuatorExt + WidgetBase + Clone + 'static {
+    /// Adapts the valuator to its rect, after creation and resize.
+    fn shape(&mut self, _rect: Rect) {}
+}
+
+impl SetpointValuator for Slider {
+    fn shape(&mut self, rect: Rect) {
+        if rect.h > rect.w {
+            self.set_type(SliderType::VerticalNice);
+        } else {
+            self.set_type(SliderType::HorizontalNice);
+        }
+    }
+}
+
+impl SetpointValuator for Dial {}
+
+pub type SetpointSlider = Setpoint<Slider>;
+pub type SetpointDial = Setpoint<Dial>;
+
+#[derive(Debug)]
+struct SetpointState {
+    dragging: bool,
+    last_publish: Instant,
+    // a change the rate limit held back
+    unsent: Option<f64>,
+}
+
+/// Publishes a numeric setpoint to dst from a Slider, vertical when higher
+/// than wide, or a Dial. Bounds and step come from min, max and step. The
+/// value the device echoes on src is shown in the label and moves the
+/// valuator when the user isn't dragging it.
+pub struct Setpoint<V: SetpointValuator> {
+    cfg: WidgetParams,
+    ctx: Context,
+    valuator: Option<V>,
+    echo: Option<f64>,
+    state: Rc<RefCell<SetpointState>>,
+    pubsub_cmd: SinkRef<PubSubCmd>,
+}
+
+impl<V: SetpointValuator> Setpoint<V> {
+    pub fn new(cfg: &WidgetParams, pubsub_cmd: SinkRef<PubSubCmd>) -> Self {
+        Self {
+            cfg: cfg.clone(),
+            ctx: Context::current(),
+            valuator: None,
+            echo: None,
+            state: Rc::new(RefCell::new(SetpointState {
+                dragging: false,
+                last_publish: Instant::now(),
+                unsent: None,
+            })),
+            pubsub_cmd,
+        }
+    }
+
+    fn configure(&mut self) {
+        let (min, max) = (self.cfg.min.unwrap_or(0.0), self.cfg.max.unwrap_or(100.0));
+        let step = self.cfg.step.unwrap_or(1.0);
+        let label = self.label();
+        let state = self.state.clone();
+        let pubsub_cmd = self.pubsub_cmd.clone();
+        let dst = self.cfg.dst_topic.clone();
+        let Some(valuator) = self.valuator.as_mut() else {
+            return;
+        };
+        valuator.set_bounds(min, max);
+        valuator.set_step(step, 1);
+        valuator.set_label(&label);
+        apply_style(valuator, &self.cfg, &self.ctx);
+        valuator.set_selection_color(self.ctx.color("valuator", Color::Blue));
+
+        let publisher = Publisher { pubsub_cmd, dst, step };
+        valuator.set_callback({
+            let state = state.clone();
+            let publisher = publisher.clone();
+            move |v| {
+                let mut state = state.borrow_mut();
+                if state.last_publish.elapsed() >= PUBLISH_INTERVAL {
+                    publisher.publish(&mut state, v.value());
+                } else {
+                    state.unsent = Some(v.value());
+                }
+            }
+        });
+        valuator.handle(move |v, ev| {
+            match ev {
+                Event::Push => state.borrow_mut().dragging = true,
+                Event::Released => {
+                    let mut state = state.borrow_mut();
+                    state.dragging = false;
+                    publisher.publish(&mut state, v.value());
+                }
+                _ => {}
+            }
+            // let the valuator do its own handling
+            false
+        });
+    }
+
+    fn label(&self) -> String {
+        let label = self.cfg.label.clone().unwrap_or_default();
+        match self.echo {
+            Some(echo) => format!(
+                "{} {:.*}{}",
+                label,
+                decimals(self.cfg.step.unwrap_or(1.0)),
+                echo,
+                self.cfg.unit.as_deref().unwrap_or("")
+            ),
+            None => label,
+        }
+    }
+
+    /// Publishes a change without a release, by keyboard or wheel, that
+    /// the rate limit held back.
+    fn flush(&mut self) {
+        let mut state = self.state.borrow_mut();
+        if let (false, Some(value)) = (state.dragging, state.unsent) {
+            let publisher = Publisher {
+                pubsub_cmd: self.pubsub_cmd.clone(),
+                dst: self.cfg.dst_topic.clone(),
+                step: self.cfg.step.unwrap_or(1.0),
+            };
+            publisher.publish(&mut state, value);
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Publisher {
+    pubsub_cmd: SinkRef<PubSubCmd>,
+    dst: Option<String>,
+    step: f64,
+}
+
+impl Publisher {
+    fn publish(&self, state: &mut SetpointState, value: f64) {
+        state.last_publish = Instant::now();
+        state.unsent = None;
+        let Some(dst) = self.dst.as_ref() else {
+            return;
+        };
+        // whole steps go out as integers
+        let payload = if self.step.fract() == 0.0 {
+            payload_encode(value.round() as i64)
+        } else {
+            payload_encode(value)
+        };
+        self.pubsub_cmd.push(PubSubCmd::Publish {
+            topic: dst.clone(),
+            payload,
+        });
+    }
+}
+
+fn decimals(step: f64) -> usize {
+    if step >= 1.0 || step <= 0.0 {
+        0
+    } else {
+        (-step.log10()).ceil() as usize
+    }
+}
+
+impl<V: SetpointValuator> PubSubWidget for Setpoint<V> {
+    fn draw(&mut self) {
+        let r = self.cfg.rect;
+        let mut valuator = V::new(r.x, r.y, r.w, r.h, None);
+        valuator.shape(r);
+        valuator.set_align(Align::Bottom | Align::Inside);
+        self.valuator = Some(valuator);
+        self.configure();
+    }
+
+    fn get_config(&self) -> WidgetParams {
+        self.cfg.clone()
+    }
+
+    fn set_config(&mut self, cfg: WidgetParams) {
+        self.cfg = cfg;
+        self.configure();
+    }
+
+    fn redraw(&mut self) {
+        self.valuator.as_mut().map(|v| v.redraw());
+    }
+
+    fn resize(&mut self, rect: Rect) {
+        self.cfg.rect = rect;
+        if let Some(valuator) = self.valuator.as_mut() {
+            valuator.resize(rect.x, rect.y, rect.w, rect.h);
+            valuator.shape(rect);
+        }
+    }
+
+    fn teardown(&mut self) {
+        self.valuator.take().map(app::delete_widget);
+    }
+
+    fn update(&mut self, event: &WidgetMsg) {
+        match event {
+            WidgetMsg::Pub { payload, .. } => {
+                let Ok(echo) = payload_as_f64(payload) else {
+                    return;
+                };
+                self.echo = Some(echo);
+                let label = self.label();
+                let dragging = self.state.borrow().dragging;
+                if let Some(valuator) = self.valuator.as_mut() {
+                    valuator.set_label(&label);
+                    if !dragging {
+                        valuator.set_value(echo);
+                    }
+                }
+            }
+            WidgetMsg::Tick => self.flush(),
+        }
+    }
+}
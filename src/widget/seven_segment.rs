@@ -0,0 +1,176 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use fltk::draw::Rect;
+use fltk::enums::{Color, FrameType};
+use fltk::frame::Frame;
+use fltk::{app, draw, prelude::*};
+
+use crate::config::file_xml::WidgetParams;
+use crate::pubsub::payload_as_f64;
+use crate::widget::meter::{draw_label, MeterValue};
+use crate::widget::theme::apply_style;
+use crate::widget::Context;
+
+use super::{PubSubWidget, WidgetMsg};
+
+const DEFAULT_DIGITS: usize = 4;
+
+// segments a..g as bits 6..0
+fn segments(c: char) -> u8 {
+    match c {
+        '0' => 0b1111110,
+        '1' => 0b0110000,
+        '2' => 0b1101101,
+        '3' => 0b1111001,
+        '4' => 0b0110011,
+        '5' => 0b1011011,
+        '6' => 0b1011111,
+        '7' => 0b1110000,
+        '8' => 0b1111111,
+        '9' => 0b1111011,
+        '-' => 0b0000001,
+        _ => 0,
+    }
+}
+
+/// Splits the formatted value in one (char, dot) per digit, right aligned.
+/// A value that doesn't fit shows as dashes.
+fn digit_cells(value: Option<f64>, digits: usize, decimals: usize) -> Vec<(char, bool)> {
+    let mut cells = Vec::new();
+    if let Some(value) = value {
+        for c in format!("{:.*}", decimals, value).chars() {
+            match (c, cells.last_mut()) {
+                ('.', Some((_, dot))) => *dot = true,
+                _ => cells.push((c, false)),
+            }
+        }
+    }
+    if cells.len() > digits {
+        return vec![('-', false); digits];
+    }
+    let mut padded = vec![(' ', false); digits - cells.len()];
+    padded.extend(cells);
+    padded
+}
+
+fn draw_digit((x, y, w, h): (i32, i32, i32, i32), bits: u8, dot: bool, on: Color, off: Color) {
+    let m = (w / 10).max(1);
+    let t = (w / 8).max(2);
+    let half = (h - 2 * m) / 2;
+    let (left, right) = (x + m, x + w - m - t);
+    let (top, mid, bottom) = (y + m, y + m + half - t / 2, y + h - m - t);
+    let horizontal = w - 2 * m - 2 * t;
+    let vertical = half - t - t / 2;
+    let rects = [
+        (left + t, top, horizontal, t), // a
+        (right, top + t, t, vertical), // b
+        (right, mid + t, t, vertical), // c
+        (left + t, bottom, horizontal, t), // d
+        (left, mid + t, t, vertical), // e
+        (left, top + t, t, vertical), // f
+        (left + t, mid, horizontal, t), // g
+    ];
+    for (idx, (sx, sy, sw, sh)) in rects.into_iter().enumerate() {
+        let lit = bits & (1 << (6 - idx)) != 0;
+        draw::draw_rect_fill(sx, sy, sw, sh, if lit { on } else { off });
+    }
+    draw::draw_rect_fill(x + w - t, bottom, t, t, if dot { on } else { off });
+}
+
+/// Numeric display in seven-segment digits, with digits and decimals
+/// attributes. The value on src is clamped to min..max and dims to the
+/// stale color after timeout msec.
+pub struct SevenSegment {
+    cfg: WidgetParams,
+    ctx: Context,
+    frame: Option<Frame>,
+    value: Rc<RefCell<MeterValue>>,
+}
+
+impl SevenSegment {
+    pub fn new(cfg: &WidgetParams) -> Self {
+        Self {
+            cfg: cfg.clone(),
+            ctx: Context::current(),
+            frame: None,
+            value: Rc::new(RefCell::new(MeterValue::new())),
+        }
+    }
+
+    fn set_draw(&mut self) {
+        let Some(frame) = self.frame.as_mut() else {
+            return;
+        };
+        let cfg = self.cfg.clone();
+        let ctx = self.ctx.clone();
+        let value = self.value.clone();
+        frame.draw(move |w| {
+            let value = value.borrow();
+            let area = draw_label(&cfg, &ctx, (w.x() + 2, w.y() + 2, w.w() - 4, w.h() - 4));
+            let digits = cfg.digits.unwrap_or(DEFAULT_DIGITS).max(1);
+            let on = value.color(&ctx, ctx.color("segment", Color::from_u32(0xFF2000)));
+            let off = ctx.color("segment_off", Color::from_u32(0x301010));
+            let (x, y, width, h) = area;
+            let dw = width / digits as i32;
+            let cells = digit_cells(value.value, digits, cfg.decimals.unwrap_or(0));
+            for (idx, (c, dot)) in cells.into_iter().enumerate() {
+                let cell = (x + idx as i32 * dw, y, dw, h);
+                draw_digit(cell, segments(c), dot, on, off);
+            }
+        });
+    }
+}
+
+impl PubSubWidget for SevenSegment {
+    fn draw(&mut self) {
+        let r = self.cfg.rect;
+        let mut frame = Frame::new(r.x, r.y, r.w, r.h, None);
+        frame.set_frame(FrameType::FlatBox);
+        apply_style(&mut frame, &self.cfg, &self.ctx);
+        if self.cfg.bg.is_none() {
+            frame.set_color(Color::Black);
+        }
+        self.frame = Some(frame);
+        self.set_draw();
+    }
+
+    fn get_config(&self) -> WidgetParams {
+        self.cfg.clone()
+    }
+
+    fn set_config(&mut self, cfg: WidgetParams) {
+        self.cfg = cfg;
+        if let Some(frame) = self.frame.as_mut() {
+            apply_style(frame, &self.cfg, &self.ctx);
+            if self.cfg.bg.is_none() {
+                frame.set_color(Color::Black);
+            }
+        }
+        self.set_draw();
+    }
+
+    fn redraw(&mut self) {
+        self.frame.as_mut().map(|f| f.redraw());
+    }
+
+    fn resize(&mut self, rect: Rect) {
+        self.cfg.rect = rect;
+        self.frame.as_mut().map(|f| f.resize(rect.x, rect.y, rect.w, rect.h));
+    }
+
+    fn teardown(&mut self) {
+        self.frame.take().map(app::delete_widget);
+    }
+
+    fn update(&mut self, event: &WidgetMsg) {
+        match event {
+            WidgetMsg::Pub { payload, .. } => {
+                if let Ok(v) = payload_as_f64(payload) {
+                    self.value.borrow_mut().set(&self.cfg, v);
+                }
+            }
+            WidgetMsg::Tick => self.value.borrow_mut().tick(&self.cfg),
+        }
+    }
+}
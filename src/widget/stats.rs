@@ -0,0 +1,235 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use fltk::draw::Rect;
+use fltk::enums::FrameType;
+use fltk::frame::Frame;
+use fltk::{app, draw, prelude::*};
+use log::warn;
+use plotters::prelude::*;
+use plotters::style::Color as PlColor;
+use plotters_bitmap::{bitmap_pixel::RGBPixel, BitMapBackend};
+
+use crate::config::file_xml::WidgetParams;
+use crate::pubsub::payload_as_f64;
+use crate::widget::theme::{apply_style, plot_color};
+use crate::widget::Context;
+
+use super::{PubSubWidget, WidgetMsg};
+
+const DEFAULT_SAMPLES: usize = 1000;
+const BINS: usize = 20;
+const TEXT_HEIGHT: i32 = 40;
+
+/// Summary of the values in the window.
+#[derive(Debug)]
+struct Summary {
+    count: usize,
+    min: f64,
+    max: f64,
+    mean: f64,
+    stddev: f64,
+    p50: f64,
+    p95: f64,
+    p99: f64,
+}
+
+impl Summary {
+    fn of(values: &[f64]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        let mut sorted = values.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let count = sorted.len();
+        let mean = sorted.iter().sum::<f64>() / count as f64;
+        let variance = sorted.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count as f64;
+        // nearest rank percentile
+        let percentile = |p: f64| sorted[((p / 100.0 * count as f64).ceil() as usize).clamp(1, count) - 1];
+        Some(Self {
+            count,
+            min: sorted[0],
+            max: sorted[count - 1],
+            mean,
+            stddev: variance.sqrt(),
+            p50: percentile(50.0),
+            p95: percentile(95.0),
+            p99: percentile(99.0),
+        })
+    }
+}
+
+/// Statistics over a sliding window of the values on src : the last
+/// samples values, within the last timespan seconds when set. Shows
+/// min/max/mean/stddev and the 50/95/99 percentiles above a histogram.
+pub struct Stats {
+    cfg: WidgetParams,
+    ctx: Context,
+    frame: Option<Frame>,
+    window: VecDeque<(f64, Instant)>,
+}
+
+impl Stats {
+    pub fn new(cfg: &WidgetParams) -> Self {
+        Self {
+            cfg: cfg.clone(),
+            ctx: Context::current(),
+            frame: None,
+            window: VecDeque::new(),
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        self.window.push_back((value, Instant::now()));
+        let samples = self.cfg.max_samples.unwrap_or(DEFAULT_SAMPLES).max(1);
+        while self.window.len() > samples {
+            self.window.pop_front();
+        }
+    }
+
+    fn expire(&mut self) {
+        if let Some(secs) = self.cfg.max_timespan {
+            let timespan = Duration::from_secs(secs.max(0) as u64);
+            while self.window.front().is_some_and(|v| v.1.elapsed() > timespan) {
+                self.window.pop_front();
+            }
+        }
+    }
+
+    fn render(&mut self) -> Result<(), String> {
+        let Some((w, h)) = self.frame.as_ref().map(|f| (f.w(), f.h())) else {
+            return Ok(());
+        };
+        if w < 50 || h <= TEXT_HEIGHT + 30 {
+            return Ok(());
+        }
+        let values: Vec<f64> = self.window.iter().map(|v| v.0).collect();
+        let summary = Summary::of(&values);
+        let bg = plot_color(self.ctx.bg(&self.cfg));
+        let fg = plot_color(self.ctx.fg(&self.cfg));
+        let bars = plot_color(self.ctx.valuator_color);
+        let unit = self.cfg.unit.clone().unwrap_or_default();
+        let mut buf = vec![0u8; (w * h * 3) as usize];
+        {
+            let drawing_area = BitMapBackend::<RGBPixel>::with_buffer_and_format(&mut buf, (w as u32, h as u32))
+                .map_err(|e| e.to_string())?
+                .into_drawing_area();
+            drawing_area.fill(&bg).map_err(|e| e.to_string())?;
+            let (text, chart_area) = drawing_area.split_vertically(TEXT_HEIGHT);
+            let style = ("sans-serif", 13).into_font().color(&fg);
+            let lines = match summary.as_ref() {
+                Some(s) => vec![
+                    format!(
+                        "{} n={} min {:.2} max {:.2} mean {:.2} sd {:.2}{}",
+                        self.cfg.label.as_deref().unwrap_or(""),
+                        s.count,
+                        s.min,
+                        s.max,
+                        s.mean,
+                        s.stddev,
+                        unit
+                    ),
+                    format!("p50 {:.2} p95 {:.2} p99 {:.2}{}", s.p50, s.p95, s.p99, unit),
+                ],
+                None => vec![format!("{} no data", self.cfg.label.as_deref().unwrap_or(""))],
+            };
+            for (idx, line) in lines.iter().enumerate() {
+                text.draw_text(line, &style, (5, 3 + idx as i32 * 18))
+                    .map_err(|e| e.to_string())?;
+            }
+            if let Some(s) = summary {
+                let (lo, hi) = (self.cfg.min.unwrap_or(s.min), self.cfg.max.unwrap_or(s.max));
+                let width = if hi > lo { (hi - lo) / BINS as f64 } else { 1.0 };
+                let mut counts = [0usize; BINS];
+                for v in values.iter() {
+                    let bin = ((v - lo) / width).floor();
+                    if bin >= 0.0 {
+                        counts[(bin as usize).min(BINS - 1)] += 1;
+                    }
+                }
+                let top = counts.iter().copied().max().unwrap_or(1).max(1);
+                let mut chart = ChartBuilder::on(&chart_area)
+                    .margin(5)
+                    .x_label_area_size(20)
+                    .y_label_area_size(35)
+                    .build_cartesian_2d(lo..lo + width * BINS as f64, 0usize..top)
+                    .map_err(|e| e.to_string())?;
+                chart
+                    .configure_mesh()
+                    .axis_style(&fg)
+                    .label_style(("sans-serif", 11).into_font().color(&fg))
+                    .bold_line_style(&fg.mix(0.2))
+                    .light_line_style(&TRANSPARENT)
+                    .x_labels(5)
+                    .y_labels(4)
+                    .draw()
+                    .map_err(|e| e.to_string())?;
+                chart
+                    .draw_series(counts.iter().enumerate().map(|(idx, count)| {
+                        let x0 = lo + idx as f64 * width;
+                        Rectangle::new([(x0, 0), (x0 + width, *count)], bars.filled())
+                    }))
+                    .map_err(|e| e.to_string())?;
+            }
+            drawing_area.present().map_err(|e| e.to_string())?;
+        }
+        self.show(&buf)
+    }
+
+    fn show(&mut self, buf: &[u8]) -> Result<(), String> {
+        match self.frame.as_mut() {
+            Some(frame) => draw::draw_rgb(frame, buf).map_err(|e| e.to_string()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl PubSubWidget for Stats {
+    fn draw(&mut self) {
+        let r = self.cfg.rect;
+        let mut frame = Frame::new(r.x, r.y, r.w, r.h, None);
+        frame.set_frame(FrameType::FlatBox);
+        apply_style(&mut frame, &self.cfg, &self.ctx);
+        self.frame = Some(frame);
+        self.redraw();
+    }
+
+    fn get_config(&self) -> WidgetParams {
+        self.cfg.clone()
+    }
+
+    fn set_config(&mut self, cfg: WidgetParams) {
+        self.cfg = cfg;
+        if let Some(frame) = self.frame.as_mut() {
+            apply_style(frame, &self.cfg, &self.ctx);
+        }
+    }
+
+    fn redraw(&mut self) {
+        if let Err(e) = self.render() {
+            warn!("Stats {:?} : {}", self.cfg.src_topic, e);
+        }
+        self.frame.as_mut().map(|f| f.redraw());
+    }
+
+    fn resize(&mut self, rect: Rect) {
+        self.cfg.rect = rect;
+        self.frame.as_mut().map(|f| f.resize(rect.x, rect.y, rect.w, rect.h));
+        self.redraw();
+    }
+
+    fn teardown(&mut self) {
+        self.frame.take().map(app::delete_widget);
+    }
+
+    fn update(&mut self, event: &WidgetMsg) {
+        match event {
+            WidgetMsg::Pub { payload, .. } => {
+                if let Ok(v) = payload_as_f64(payload) {
+                    self.add(v);
+                }
+            }
+            WidgetMsg::Tick => self.expire(),
+        }
+    }
+}
@@ -12,22 +12,23 @@ use std::rc::Rc;
 use std::time::Instant;
 use std::time::SystemTime;
 
-use crate::pubsub::PubSubEvent;
+use crate::pubsub::{payload_as_f64, PubSubEvent};
 use crate::widget:: hms;
 use crate::widget::Context;
+use crate::widget::theme::apply_style;
 use crate::config::file_xml::WidgetParams; 
+use super::{PubSubWidget, WidgetMsg};
 use tokio::sync::mpsc;
 
 use evalexpr::Value as V;
 use evalexpr::*;
 
-#[derive(Debug, Clone)]
+/// Round gauge for the number on src, between min and max.
 pub struct SubGauge {
-    value: f64,
-    last_update: SystemTime,
-    eval_expr: Option<Node>,
+    value: Rc<RefCell<f64>>,
     widget_params: WidgetParams,
     ctx: Context,
+    frame: Option<frame::Frame>,
 }
 
 fn clap(x:f64,min:f64,max:f64) -> f64 {
@@ -41,36 +42,36 @@ fn clap(x:f64,min:f64,max:f64) -> f64 {
 }
 
 impl SubGauge {
-    pub fn new(cfg:&WidgetParams) -> Self {
+    pub fn new(cfg:&WidgetParams, ctx: &Context) -> Self {
 
  //       grp.handle(move |w, ev| dnd_callback(&mut w.as_base_widget(), ev));
         SubGauge {
-            value: 0.0,
-            last_update: std::time::UNIX_EPOCH,
-            eval_expr: None,
+            value: Rc::new(RefCell::new(0.0)),
             widget_params: cfg.clone(),
-            ctx: Context::new(),
+            ctx: ctx.clone(),
+            frame: None,
         }
     }
 
-    pub fn draw(&mut self) {
-        let cfg = &self.widget_params;
-        let mut grp = group::Group::default().with_align(Align::Top);
-        let mut frame = frame::Frame::new(cfg.rect.x,cfg.rect.y,cfg.rect.w,cfg.rect.h,None);
-        frame.set_frame(FrameType::BorderBox);
-        frame.set_color(Color::White);
-
+    // the colours and range are read when drawing, after set_config too
+    fn set_draw(&mut self) {
+        let Some(frame) = self.frame.as_mut() else {
+            return;
+        };
+        let bg = self.ctx.bg(&self.widget_params);
+        let fg = self.ctx.fg(&self.widget_params);
+        let valuator = self.ctx.valuator_color;
         let min = self.widget_params.min.unwrap_or(0.);
         let max = self.widget_params.max.unwrap_or(100.);
-        let value = clap(self.value,min,max);
-        let angle = (1. - (value-min)/(max-min)) * 270. - 45.;
+        let value = self.value.clone();
         frame.draw(move|w| {
-            info!("SubGauge::draw() w={},{},{},{}", w.x(),w.y(),w.w(),w.h());
-            draw::set_draw_color(Color::Black);
+            let value = clap(*value.borrow(),min,max);
+            let angle = (1. - (value-min)/(max-min)) * 270. - 45.;
+            draw::set_draw_color(fg);
             draw::draw_pie(w.x(), w.y(), w.w(), w.h(), -45., 225.); // total angle 270
-            draw::set_draw_color(Color::Green);
+            draw::set_draw_color(valuator);
             draw::draw_pie(w.x(), w.y(), w.w(), w.h(), angle, 225.);
-            draw::set_draw_color(Color::White);
+            draw::set_draw_color(bg);
             draw::draw_pie(
                 w.x() + w.w() / 10,
                 w.y() + w.h() / 10,
@@ -82,15 +83,12 @@ impl SubGauge {
             let (center_x, center_y) = (w.x() + w.w() / 2, w.y() + w.h() / 2);
             let x2 = center_x as f64 + (w.w() / 2 - 10) as f64 * angle.to_radians().cos();
             let y2 = center_y as f64 - (w.h() / 2 - 10) as f64 * angle.to_radians().sin();
-            draw::set_draw_color(Color::Black);
+            draw::set_draw_color(fg);
             draw::set_line_style(LineStyle::Solid, 3);
             draw::draw_line(center_x, center_y, x2 as i32, y2 as i32);
+            draw::set_line_style(LineStyle::Solid, 0);
         });
-
         frame.redraw();
-        frame.show();
-        grp.end();
-
     }
 
     fn get_major_minor_ticks(min:f64,max:f64) -> (Vec<f64>,Vec<f64>) {
@@ -116,4 +114,46 @@ impl SubGauge {
     
 }
 
+impl PubSubWidget for SubGauge {
+    fn draw(&mut self) {
+        let cfg = &self.widget_params;
+        let mut frame = frame::Frame::new(cfg.rect.x,cfg.rect.y,cfg.rect.w,cfg.rect.h,None);
+        frame.set_frame(FrameType::BorderBox);
+        apply_style(&mut frame, &self.widget_params, &self.ctx);
+        self.frame = Some(frame);
+        self.set_draw();
+    }
+
+    fn get_config(&self) -> WidgetParams {
+        self.widget_params.clone()
+    }
+
+    fn set_config(&mut self, cfg: WidgetParams) {
+        self.widget_params = cfg;
+        if let Some(frame) = self.frame.as_mut() {
+            apply_style(frame, &self.widget_params, &self.ctx);
+        }
+        self.set_draw();
+    }
+
+    fn redraw(&mut self) {
+        self.frame.as_mut().map(|f| f.redraw());
+    }
+
+    fn resize(&mut self, rect: Rect) {
+        self.widget_params.rect = rect;
+        self.frame.as_mut().map(|f| f.resize(rect.x, rect.y, rect.w, rect.h));
+    }
+
+    fn teardown(&mut self) {
+        self.frame.take().map(app::delete_widget);
+    }
 
+    fn update(&mut self, event: &WidgetMsg) {
+        if let WidgetMsg::Pub { payload, .. } = event {
+            if let Ok(value) = payload_as_f64(payload) {
+                *self.value.borrow_mut() = value;
+            }
+        }
+    }
+}
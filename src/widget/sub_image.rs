@@ -0,0 +1,204 @@
+use std::collections::BTreeMap;
+
+use fltk::draw::Rect;
+use fltk::enums::{Align, FrameType};
+use fltk::frame::Frame;
+use fltk::image::{JpegImage, PngImage, SharedImage, SvgImage};
+use fltk::{app, prelude::*};
+use log::{info, warn};
+use minicbor::Decoder;
+
+use crate::config::file_xml::WidgetParams;
+use crate::pubsub::{payload_as_f64, payload_decode};
+use crate::widget::theme::apply_style;
+use crate::widget::Context;
+
+use super::{PubSubWidget, WidgetMsg};
+
+/// Shows the image file of the image attribute. With an images attribute
+/// like "open:door_open.png,closed:door_closed.png" the value on src picks
+/// the file, values without an entry fall back to image. A src payload that
+/// is itself a PNG, JPEG or SVG image, raw or as CBOR bytes, is shown as is.
+pub struct SubImage {
+    cfg: WidgetParams,
+    ctx: Context,
+    frame: Option<Frame>,
+    images: BTreeMap<String, String>,
+    cache: BTreeMap<String, SharedImage>,
+    current: Option<String>,
+}
+
+impl SubImage {
+    pub fn new(cfg: &WidgetParams) -> Self {
+        Self {
+            cfg: cfg.clone(),
+            ctx: Context::current(),
+            frame: None,
+            images: parse_images(cfg.images.as_deref().unwrap_or("")),
+            cache: BTreeMap::new(),
+            current: cfg.image.clone(),
+        }
+    }
+
+    fn show_file(&mut self, file: Option<String>) {
+        self.current = file.clone();
+        let image = file.and_then(|file| match self.cache.get(&file) {
+            Some(image) => Some(image.clone()),
+            None => match SharedImage::load(&file) {
+                Ok(image) => {
+                    info!("Loaded image {}", file);
+                    self.cache.insert(file, image.clone());
+                    Some(image)
+                }
+                Err(e) => {
+                    warn!("Cannot load image {} : {}", file, e);
+                    None
+                }
+            },
+        });
+        if let Some(frame) = self.frame.as_mut() {
+            match image {
+                Some(mut image) => {
+                    image.scale(frame.w(), frame.h(), true, true);
+                    frame.set_image(Some(image));
+                }
+                None => frame.set_image(None::<SharedImage>),
+            }
+        }
+    }
+
+    fn show_data(&mut self, data: &[u8]) {
+        let Some(frame) = self.frame.as_mut() else {
+            return;
+        };
+        let (w, h) = (frame.w(), frame.h());
+        let shown = match image_kind(data) {
+            Some(ImageKind::Png) => PngImage::from_data(data).map(|mut i| {
+                i.scale(w, h, true, true);
+                frame.set_image(Some(i));
+            }),
+            Some(ImageKind::Jpeg) => JpegImage::from_data(data).map(|mut i| {
+                i.scale(w, h, true, true);
+                frame.set_image(Some(i));
+            }),
+            Some(ImageKind::Svg) => SvgImage::from_data(&String::from_utf8_lossy(data)).map(|mut i| {
+                i.scale(w, h, true, true);
+                frame.set_image(Some(i));
+            }),
+            None => return,
+        };
+        if let Err(e) = shown {
+            warn!("Invalid image in payload : {}", e);
+        }
+    }
+}
+
+enum ImageKind {
+    Png,
+    Jpeg,
+    Svg,
+}
+
+fn image_kind(data: &[u8]) -> Option<ImageKind> {
+    if data.starts_with(&[0x89, b'P', b'N', b'G']) {
+        Some(ImageKind::Png)
+    } else if data.starts_with(&[0xFF, 0xD8]) {
+        Some(ImageKind::Jpeg)
+    } else if data.starts_with(b"<svg") || data.starts_with(b"<?xml") {
+        Some(ImageKind::Svg)
+    } else {
+        None
+    }
+}
+
+/// The image bytes of a payload, sent raw or as a CBOR byte string.
+fn image_data(payload: &Vec<u8>) -> Option<&[u8]> {
+    if image_kind(payload).is_some() {
+        return Some(payload.as_slice());
+    }
+    Decoder::new(payload)
+        .bytes()
+        .ok()
+        .filter(|data| image_kind(data).is_some())
+}
+
+/// The payload as the text used for the images lookup.
+fn payload_text(payload: &Vec<u8>) -> Option<String> {
+    if let Ok(s) = payload_decode::<&str>(payload) {
+        return Some(s.to_string());
+    }
+    if let Ok(b) = payload_decode::<bool>(payload) {
+        return Some(b.to_string());
+    }
+    payload_as_f64(payload).ok().map(|v| {
+        if v.fract() == 0.0 {
+            format!("{}", v as i64)
+        } else {
+            v.to_string()
+        }
+    })
+}
+
+fn parse_images(images: &str) -> BTreeMap<String, String> {
+    images
+        .split(',')
+        .filter_map(|entry| entry.split_once(':'))
+        .map(|(value, file)| (value.trim().to_string(), file.trim().to_string()))
+        .collect()
+}
+
+impl PubSubWidget for SubImage {
+    fn draw(&mut self) {
+        let r = self.cfg.rect;
+        let mut frame = Frame::new(r.x, r.y, r.w, r.h, None);
+        frame.set_frame(FrameType::NoBox);
+        frame.set_align(Align::Bottom | Align::Inside | Align::ImageBackdrop);
+        apply_style(&mut frame, &self.cfg, &self.ctx);
+        self.cfg.label.as_ref().map(|s| frame.set_label(s.as_str()));
+        self.frame = Some(frame);
+        self.show_file(self.current.clone());
+    }
+
+    fn get_config(&self) -> WidgetParams {
+        self.cfg.clone()
+    }
+
+    fn set_config(&mut self, cfg: WidgetParams) {
+        self.images = parse_images(cfg.images.as_deref().unwrap_or(""));
+        self.cfg = cfg;
+        if let Some(frame) = self.frame.as_mut() {
+            apply_style(frame, &self.cfg, &self.ctx);
+            frame.set_label(self.cfg.label.as_deref().unwrap_or(""));
+        }
+        self.show_file(self.cfg.image.clone());
+    }
+
+    fn redraw(&mut self) {
+        self.frame.as_mut().map(|f| f.redraw());
+    }
+
+    fn resize(&mut self, rect: Rect) {
+        self.cfg.rect = rect;
+        self.frame.as_mut().map(|f| f.resize(rect.x, rect.y, rect.w, rect.h));
+        self.show_file(self.current.clone());
+    }
+
+    fn teardown(&mut self) {
+        self.frame.take().map(app::delete_widget);
+    }
+
+    fn update(&mut self, event: &WidgetMsg) {
+        if let WidgetMsg::Pub { payload, .. } = event {
+            if let Some(data) = image_data(payload) {
+                self.show_data(data);
+                return;
+            }
+            let file = payload_text(payload)
+                .and_then(|text| self.images.get(&text).cloned())
+                .or(self.cfg.image.clone());
+            if file != self.current {
+                self.show_file(file);
+            }
+        }
+    }
+}
@@ -10,12 +10,14 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
 use std::time::{Duration, Instant};
-use std::time::SystemTime;
 
 use crate::config::file_xml::WidgetParams;
-use crate::pubsub::{payload_as_f64, payload_decode, PubSubEvent};
+use crate::pubsub::topic_matcher::expand_captures;
+use crate::pubsub::{payload_as_f64, payload_decode, payload_encode, PubSubEvent};
 use crate::widget::hms;
+use crate::widget::notify::Notifier;
 use crate::widget::Context;
+use crate::widget::theme::apply_style;
 use crate::WidgetMsg;
 use tokio::sync::mpsc;
 
@@ -24,30 +26,45 @@ use evalexpr::*;
 
 use super::PubSubWidget;
 
-#[derive(Debug, Clone)]
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(3000);
+
+#[derive(Clone)]
 pub struct SubLabel {
     value: f64,
-    last_update: SystemTime,
+    // None until a value is received, a label never updated isn't stale
+    last_update: Option<Instant>,
     timeout : Duration,
     eval_expr: Option<Node>,
     cfg: WidgetParams,
     ctx: Context,
     frame: Option<fltk::frame::Frame>,
-    
+    stale: bool,
+    notifier: Rc<RefCell<Notifier>>,
 }
 
 impl SubLabel {
-    pub fn new(cfg: &WidgetParams) -> Self {
+    pub fn new(cfg: &WidgetParams, notifier: Rc<RefCell<Notifier>>) -> Self {
         Self {
             value: 0.0,
-            last_update: std::time::UNIX_EPOCH,
-            timeout : Duration::from_millis(cfg.timeout.unwrap_or(3) as u64),
+            last_update: None,
+            timeout : cfg.timeout.map_or(DEFAULT_TIMEOUT, |ms| Duration::from_millis(ms as u64)),
             eval_expr: None,
             cfg: cfg.clone(),
-            ctx: Context::new(),
+            ctx: Context::current(),
             frame: None,
+            stale: false,
+            notifier,
         }
     }
+
+    fn show_value(&mut self, captures: &[String]) {
+        let binding = "".to_string();
+        // a prefix like "$1 : " shows which device matched the wildcard
+        let prefix = expand_captures(self.cfg.prefix.as_ref().unwrap_or(&binding), captures);
+        let suffix = self.cfg.suffix.as_ref().unwrap_or(&binding);
+        let line = format!("{}{:.2}{}", prefix, self.value, suffix);
+        self.frame.as_mut().map(|f| f.set_label(&line));
+    }
 }
 
 impl PubSubWidget for SubLabel {
@@ -60,77 +77,87 @@ impl PubSubWidget for SubLabel {
             None,
         );
         frame.set_frame(FrameType::ThinUpBox);
-        frame.set_color(Color::from_u32(0xFFFFFF));
         frame.set_label_font(Font::HelveticaBold);
         frame.set_align(Align::Center);
-        let label = self.cfg.label.as_ref().unwrap().clone();
+        apply_style(&mut frame, &self.cfg, &self.ctx);
         self.cfg.label.as_ref().map(|s| frame.set_label(s.as_str()));
 
-        let origins = (self.cfg.rect.x, self.cfg.rect.y);
-        frame.handle({
-            let mut x = 0;
-            let mut y = 0;
-            let mut new_x = 0;
-            let mut new_y = 0;
-            let mut origins = origins;
-            move |w, ev| match ev {
-                enums::Event::Push => {
-                    if app::event_mouse_button() == app::MouseButton::Right {
-                        (x, y) = app::event_coords();
-                        true
-                    } else {
-                        false
-                    }
-                }
-                enums::Event::Drag => {
-                    if app::event_mouse_button() == app::MouseButton::Right {
-                        info!("Dragging");
-                        let (x1, y1) = app::event_coords();
-                        (new_x, new_y) = (origins.0 + x1 - x, origins.1 + y1 - y);
-                        w.set_pos(new_x, new_y);
-                        true
-                    } else {
-                        false
-                    }
-                }
-                enums::Event::Released => {
-                    if app::event_mouse_button() == app::MouseButton::Right {
-                        origins = (new_x, new_y);
-                        true
-                    } else {
-                        false
-                    }
-                }
-                _ev => false,
-            }
-        });
         self.frame = Some(frame);
     }
 
+    fn get_config(&self) -> WidgetParams {
+        self.cfg.clone()
+    }
+
+    fn set_config(&mut self, cfg: WidgetParams) {
+        self.cfg = cfg;
+        self.timeout = self.cfg.timeout.map_or(DEFAULT_TIMEOUT, |ms| Duration::from_millis(ms as u64));
+        if let Some(frame) = self.frame.as_mut() {
+            apply_style(frame, &self.cfg, &self.ctx);
+            if self.last_update.is_none() {
+                self.cfg.label.as_ref().map(|s| frame.set_label(s.as_str()));
+            }
+            frame.redraw();
+        }
+    }
+
+    fn redraw(&mut self) {
+        self.frame.as_mut().map(|f| f.redraw());
+    }
+
+    fn on_connection(&mut self, connected: bool) {
+        if !connected {
+            let stale = self.ctx.color("stale", Color::from_u32(0x808080));
+            self.frame.as_mut().map(|f| f.set_color(stale));
+        }
+    }
+
+    fn resize(&mut self, rect: Rect) {
+        self.cfg.rect = rect;
+        self.frame.as_mut().map(|f| f.resize(rect.x, rect.y, rect.w, rect.h));
+    }
+
+    fn save_state(&self) -> Option<Vec<u8>> {
+        // only a value that was received is worth carrying over
+        self.last_update.is_some().then(|| payload_encode(self.value))
+    }
+
+    fn restore_state(&mut self, state: &Vec<u8>) {
+        if let Ok(v) = payload_as_f64(state) {
+            self.value = v;
+            self.show_value(&[]);
+        }
+    }
+
+    fn teardown(&mut self) {
+        self.notifier.borrow_mut().clear_widget(&self.cfg);
+        self.frame.take().map(app::delete_widget);
+    }
+
     fn update(&mut self, event: &WidgetMsg) {
         match event {
-            WidgetMsg::Pub { topic, payload } => {
-
-
-                let topic_cfg = self.cfg.src_topic.as_ref().clone();
-                if *topic == *topic_cfg.unwrap() {
-                    self.last_update = SystemTime::now();
-                    self.frame.as_mut().map(|f: &mut frame::Frame| f.set_color(Color::from_u32(0xFFFFFF)));
-                    let _ = payload_as_f64(&payload).and_then(|v| {
-                        self.value = v;
-                        let binding = "".to_string();
-                        let suffix = self.cfg.suffix.as_ref().unwrap_or(&binding);
-                        let line = format!("{:.2}{}", self.value, suffix);
-                        self.frame.as_mut().map(|f| f.set_label(&line));
-                        Ok(())
-                    });
+            WidgetMsg::Pub { topic, payload, captures } => {
+                self.last_update = Some(Instant::now());
+                if self.stale {
+                    self.stale = false;
+                    self.notifier.borrow_mut().clear_widget(&self.cfg);
                 }
+                let bg = self.ctx.bg(&self.cfg);
+                self.frame.as_mut().map(|f: &mut frame::Frame| f.set_color(bg));
+                let _ = payload_as_f64(&payload).and_then(|v| {
+                    self.value = v;
+                    self.show_value(captures);
+                    Ok(())
+                });
             }
             WidgetMsg::Tick => {
-                let now = SystemTime::now();
-                let elapsed = now.duration_since(self.last_update).unwrap();
-                if elapsed > self.timeout {
-                    self.frame.as_mut().map(|f| f.set_color(Color::from_u32(0x808080)));
+                let elapsed = self.last_update.map_or(Duration::ZERO, |t| t.elapsed());
+                if elapsed > self.timeout && !self.stale {
+                    self.stale = true;
+                    let stale = self.ctx.color("stale", Color::from_u32(0x808080));
+                    self.frame.as_mut().map(|f| f.set_color(stale));
+                    let text = format!("{} timed out", self.cfg.label.as_deref().unwrap_or(&self.cfg.name));
+                    self.notifier.borrow_mut().notify_widget(&self.cfg, self.frame.as_ref(), &text);
                 }
             }
         }
@@ -0,0 +1,226 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use fltk::button::ToggleButton;
+use fltk::draw::Rect;
+use fltk::enums::{Color, Font};
+use fltk::{app, dialog, prelude::*};
+use log::info;
+
+use crate::config::file_xml::WidgetParams;
+use crate::limero::{SinkRef, SinkTrait};
+use crate::pubsub::{payload_decode, PubSubCmd};
+use crate::widget::pub_button::to_cbor;
+use crate::widget::theme::apply_style;
+use crate::widget::Context;
+
+use super::{PubSubWidget, WidgetMsg};
+
+const DEFAULT_CONFIRM_TIMEOUT: Duration = Duration::from_millis(3000);
+
+/// What the switch knows about the device, shared with the button callback.
+#[derive(Debug, Default)]
+struct SwitchState {
+    // last state reported on src
+    reported: Option<bool>,
+    // state asked for and not yet confirmed
+    pending: Option<(bool, Instant)>,
+    // the device didn't follow the last request
+    mismatch: bool,
+}
+
+/// Latching toggle : a click publishes on or off to dst, the shown state
+/// follows what the device reports on src. Until the device confirms, the
+/// switch shows as pending; when it doesn't within timeout msec, as alarm.
+/// With a confirm attribute, switching on asks that question first.
+/// Without src the switch trusts its own requests.
+pub struct Switch {
+    cfg: WidgetParams,
+    ctx: Context,
+    button: Option<ToggleButton>,
+    state: Rc<RefCell<SwitchState>>,
+    pubsub_cmd: SinkRef<PubSubCmd>,
+}
+
+impl Switch {
+    pub fn new(cfg: &WidgetParams, pubsub_cmd: SinkRef<PubSubCmd>) -> Self {
+        Self {
+            cfg: cfg.clone(),
+            ctx: Context::current(),
+            button: None,
+            state: Rc::new(RefCell::new(SwitchState::default())),
+            pubsub_cmd,
+        }
+    }
+
+    fn on_value(&self) -> String {
+        self.cfg.on.clone().unwrap_or("true".to_string())
+    }
+
+    fn off_value(&self) -> String {
+        self.cfg.off.clone().unwrap_or("false".to_string())
+    }
+
+    fn timeout(&self) -> Duration {
+        self.cfg
+            .timeout
+            .map_or(DEFAULT_CONFIRM_TIMEOUT, |ms| Duration::from_millis(ms as u64))
+    }
+
+    /// Maps a src payload to on or off, by the on and off values or as a bool.
+    fn feedback(&self, payload: &Vec<u8>) -> Option<bool> {
+        if *payload == to_cbor(&self.on_value()) {
+            Some(true)
+        } else if *payload == to_cbor(&self.off_value()) {
+            Some(false)
+        } else {
+            payload_decode::<bool>(payload).ok()
+        }
+    }
+
+    fn set_callback(&mut self) {
+        let state = self.state.clone();
+        let cfg = self.cfg.clone();
+        let ctx = self.ctx.clone();
+        let pubsub_cmd = self.pubsub_cmd.clone();
+        let (on_value, off_value) = (self.on_value(), self.off_value());
+        let Some(button) = self.button.as_mut() else {
+            return;
+        };
+        button.set_callback(move |b| {
+            let current = {
+                let state = state.borrow();
+                state.pending.map(|(on, _)| on).or(state.reported).unwrap_or(false)
+            };
+            let wanted = !current;
+            let confirmed = match (&cfg.confirm, wanted) {
+                (Some(question), true) => dialog::choice2_default(question, "No", "Yes", "") == Some(1),
+                _ => true,
+            };
+            if confirmed {
+                if let Some(dst) = cfg.dst_topic.as_ref() {
+                    info!("Switch {} {}", dst, if wanted { "on" } else { "off" });
+                    pubsub_cmd.push(PubSubCmd::Publish {
+                        topic: dst.clone(),
+                        payload: to_cbor(if wanted { &on_value } else { &off_value }),
+                    });
+                }
+                let mut state = state.borrow_mut();
+                if cfg.src_topic.is_some() {
+                    state.pending = Some((wanted, Instant::now()));
+                } else {
+                    state.reported = Some(wanted);
+                }
+                state.mismatch = false;
+            }
+            show_state(b, &cfg, &ctx, &state.borrow());
+        });
+    }
+}
+
+fn show_state(button: &mut ToggleButton, cfg: &WidgetParams, ctx: &Context, state: &SwitchState) {
+    let label = cfg.label.clone().unwrap_or_default();
+    let (on, color, mark) = match (state.pending, state.reported) {
+        (Some((on, _)), _) => (on, ctx.color("pending", Color::from_u32(0xFFC000)), " ..."),
+        (None, _) if state.mismatch => (
+            state.reported.unwrap_or(false),
+            ctx.color("alarm", Color::from_u32(0xFF0000)),
+            " ?",
+        ),
+        (None, Some(true)) => (true, ctx.color("ok", Color::from_u32(0x00C000)), ""),
+        (None, _) => (false, ctx.color("button", Color::Blue), ""),
+    };
+    button.set_value(on);
+    button.set_color(color);
+    button.set_selection_color(color);
+    button.set_label(&format!("{}{}", label, mark));
+    button.redraw();
+}
+
+impl PubSubWidget for Switch {
+    fn draw(&mut self) {
+        let r = self.cfg.rect;
+        let mut button = ToggleButton::new(r.x, r.y, r.w, r.h, None);
+        button.set_label_font(Font::HelveticaBold);
+        apply_style(&mut button, &self.cfg, &self.ctx);
+        if self.cfg.fg.is_none() {
+            button.set_label_color(self.ctx.color("button_text", Color::White));
+        }
+        show_state(&mut button, &self.cfg, &self.ctx, &self.state.borrow());
+        self.button = Some(button);
+        self.set_callback();
+    }
+
+    fn get_config(&self) -> WidgetParams {
+        self.cfg.clone()
+    }
+
+    fn set_config(&mut self, cfg: WidgetParams) {
+        self.cfg = cfg;
+        if let Some(button) = self.button.as_mut() {
+            apply_style(button, &self.cfg, &self.ctx);
+            show_state(button, &self.cfg, &self.ctx, &self.state.borrow());
+        }
+        self.set_callback();
+    }
+
+    fn redraw(&mut self) {
+        self.button.as_mut().map(|b| b.redraw());
+    }
+
+    fn on_connection(&mut self, connected: bool) {
+        if !connected {
+            // whatever was reported may no longer be true
+            let mut state = self.state.borrow_mut();
+            state.reported = None;
+            state.pending = None;
+        }
+        if let Some(button) = self.button.as_mut() {
+            show_state(button, &self.cfg, &self.ctx, &self.state.borrow());
+        }
+    }
+
+    fn resize(&mut self, rect: Rect) {
+        self.cfg.rect = rect;
+        self.button.as_mut().map(|b| b.resize(rect.x, rect.y, rect.w, rect.h));
+    }
+
+    fn teardown(&mut self) {
+        self.button.take().map(app::delete_widget);
+    }
+
+    fn update(&mut self, event: &WidgetMsg) {
+        match event {
+            WidgetMsg::Pub { payload, .. } => {
+                let Some(on) = self.feedback(payload) else {
+                    return;
+                };
+                let mut state = self.state.borrow_mut();
+                state.reported = Some(on);
+                match state.pending {
+                    Some((wanted, _)) if wanted == on => {
+                        state.pending = None;
+                        state.mismatch = false;
+                    }
+                    // still waiting, the device may report its old state first
+                    Some(_) => {}
+                    None => state.mismatch = false,
+                }
+            }
+            WidgetMsg::Tick => {
+                let timeout = self.timeout();
+                let mut state = self.state.borrow_mut();
+                if let Some((_, since)) = state.pending {
+                    if since.elapsed() > timeout {
+                        state.pending = None;
+                        state.mismatch = true;
+                    }
+                }
+            }
+        }
+        if let Some(button) = self.button.as_mut() {
+            show_state(button, &self.cfg, &self.ctx, &self.state.borrow());
+        }
+    }
+}
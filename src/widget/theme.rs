@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+
+use fltk::enums::{Color, Font, FrameType};
+use fltk::prelude::*;
+use fltk_theme::{color_themes, ColorTheme, SchemeType, WidgetScheme};
+use log::{info, warn};
+use plotters::style::RGBColor;
+
+use crate::config::file_xml::WidgetParams;
+use crate::widget::Context;
+
+/// Builds the widget context from the Dashboard's theme, scheme and palette
+/// attributes and applies the theme to the fltk application.
+pub fn apply_theme(dashboard: &WidgetParams, palette: &BTreeMap<String, String>) -> Context {
+    let mut ctx = Context::new();
+    let theme = dashboard.theme.clone().unwrap_or("light".to_string());
+    info!("Applying theme {}", theme);
+    match theme.as_str() {
+        "light" => {}
+        "dark" => {
+            ColorTheme::new(color_themes::DARK_THEME).apply();
+            ctx.background_color = Color::from_u32(0x333333);
+            ctx.font_color = Color::from_u32(0xE0E0E0);
+            ctx.valuator_color = Color::from_u32(0x00C000);
+        }
+        "black" => {
+            ColorTheme::new(color_themes::BLACK_THEME).apply();
+            ctx.background_color = Color::Black;
+            ctx.font_color = Color::from_u32(0xE0E0E0);
+            ctx.valuator_color = Color::from_u32(0x00C000);
+        }
+        "gray" => {
+            ColorTheme::new(color_themes::GRAY_THEME).apply();
+            ctx.background_color = Color::from_u32(0xC0C0C0);
+        }
+        "tan" => {
+            ColorTheme::new(color_themes::TAN_THEME).apply();
+            ctx.background_color = Color::from_u32(0xD2B48C);
+        }
+        "shake" => {
+            ColorTheme::new(color_themes::SHAKE_THEME).apply();
+        }
+        _ => warn!("Unknown theme {}", theme),
+    }
+    ctx.theme = Some(theme);
+    if let Some(scheme) = dashboard.scheme.as_ref() {
+        match scheme.as_str() {
+            "aqua" => WidgetScheme::new(SchemeType::Aqua).apply(),
+            "clean" => WidgetScheme::new(SchemeType::Clean).apply(),
+            "crystal" => WidgetScheme::new(SchemeType::Crystal).apply(),
+            "fluent" => WidgetScheme::new(SchemeType::Fluent).apply(),
+            "gleam" => WidgetScheme::new(SchemeType::Gleam).apply(),
+            _ => warn!("Unknown scheme {}", scheme),
+        }
+    }
+    // palette entries may refer to each other in any order, so resolve
+    // until a pass adds nothing new
+    let mut unresolved: Vec<(&String, &String)> = palette.iter().collect();
+    loop {
+        let before = unresolved.len();
+        unresolved.retain(|(name, value)| match parse_color(value, &ctx) {
+            Some(color) => {
+                ctx.palette.insert((*name).clone(), color);
+                false
+            }
+            None => true,
+        });
+        if unresolved.is_empty() || unresolved.len() == before {
+            break;
+        }
+    }
+    for (name, value) in unresolved {
+        warn!("Invalid palette colour {}={}", name, value);
+    }
+    ctx.background_color = ctx.color("background", ctx.background_color);
+    ctx.font_color = ctx.color("font", ctx.font_color);
+    ctx.valuator_color = ctx.color("valuator", ctx.valuator_color);
+    if dashboard.bg.is_some() {
+        ctx.background_color = ctx.bg(dashboard);
+    }
+    if dashboard.fg.is_some() {
+        ctx.font_color = ctx.fg(dashboard);
+    }
+    // the colours only go to the dashboard window and widgets, dialogs and
+    // other fltk defaults keep those of the theme
+    ctx
+}
+
+/// Applies the colours, font and frame of the config to an fltk widget.
+pub fn apply_style<W: WidgetExt>(w: &mut W, cfg: &WidgetParams, ctx: &Context) {
+    w.set_color(ctx.bg(cfg));
+    w.set_label_color(ctx.fg(cfg));
+    cfg.font
+        .as_ref()
+        .and_then(|f| parse_font(f))
+        .map(|f| w.set_label_font(f));
+    cfg.text_size.map(|s| w.set_label_size(s));
+    cfg.frame
+        .as_ref()
+        .and_then(|f| parse_frame(f))
+        .map(|f| w.set_frame(f));
+}
+
+/// The same color for plotters charts.
+pub fn plot_color(color: Color) -> RGBColor {
+    let (r, g, b) = color.to_rgb();
+    RGBColor(r, g, b)
+}
+
+/// Accepts a palette name, #RRGGBB or one of the basic colour names.
+pub fn parse_color(value: &str, ctx: &Context) -> Option<Color> {
+    if let Some(color) = ctx.palette.get(value) {
+        return Some(*color);
+    }
+    if value.starts_with('#') {
+        return Color::from_hex_str(value).ok();
+    }
+    match value.to_lowercase().as_str() {
+        "black" => Some(Color::Black),
+        "white" => Some(Color::White),
+        "red" => Some(Color::Red),
+        "green" => Some(Color::Green),
+        "blue" => Some(Color::Blue),
+        "yellow" => Some(Color::Yellow),
+        "cyan" => Some(Color::Cyan),
+        "magenta" => Some(Color::Magenta),
+        "gray" | "grey" => Some(Color::from_u32(0x808080)),
+        "orange" | "amber" => Some(Color::from_u32(0xFFA000)),
+        "dark" => Some(Color::from_u32(0x333333)),
+        _ => None,
+    }
+}
+
+pub fn parse_font(value: &str) -> Option<Font> {
+    match value.to_lowercase().as_str() {
+        "helvetica" => Some(Font::Helvetica),
+        "helvetica bold" => Some(Font::HelveticaBold),
+        "courier" => Some(Font::Courier),
+        "courier bold" => Some(Font::CourierBold),
+        "times" => Some(Font::Times),
+        "times bold" => Some(Font::TimesBold),
+        "screen" => Some(Font::Screen),
+        "screen bold" => Some(Font::ScreenBold),
+        "symbol" => Some(Font::Symbol),
+        "zapf" => Some(Font::Zapfdingbats),
+        _ => {
+            warn!("Unknown font {}", value);
+            None
+        }
+    }
+}
+
+pub fn parse_frame(value: &str) -> Option<FrameType> {
+    match value.to_lowercase().as_str() {
+        "none" => Some(FrameType::NoBox),
+        "flat" => Some(FrameType::FlatBox),
+        "up" => Some(FrameType::UpBox),
+        "down" => Some(FrameType::DownBox),
+        "thin_up" => Some(FrameType::ThinUpBox),
+        "thin_down" => Some(FrameType::ThinDownBox),
+        "border" => Some(FrameType::BorderBox),
+        "engraved" => Some(FrameType::EngravedBox),
+        "embossed" => Some(FrameType::EmbossedBox),
+        "round" => Some(FrameType::RoundUpBox),
+        "rounded" => Some(FrameType::RoundedBox),
+        "plastic" => Some(FrameType::PlasticUpBox),
+        "gtk" => Some(FrameType::GtkUpBox),
+        _ => {
+            warn!("Unknown frame {}", value);
+            None
+        }
+    }
+}
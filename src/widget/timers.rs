@@ -0,0 +1,55 @@
+use std::time::{Duration, Instant};
+
+use crate::widget::PubSubWidget;
+
+/// The Tick period of widgets without a msec attribute.
+pub const DEFAULT_TICK: Duration = Duration::from_millis(1000);
+
+/// One repeating timer per widget, checked from the frames of the UI thread.
+/// Ticks don't travel through the message queue, so a busy bus can't hold
+/// them back. The resolution is one frame, 1000/fps msec : a shorter period
+/// still ticks only once per frame.
+pub struct WidgetTimers {
+    timers: Vec<Option<(Duration, Instant)>>,
+}
+
+impl WidgetTimers {
+    pub fn new(widgets: &[Box<dyn PubSubWidget>]) -> Self {
+        let now = Instant::now();
+        Self {
+            timers: widgets
+                .iter()
+                .map(|w| w.tick_period().map(|period| (period, now + period)))
+                .collect(),
+        }
+    }
+
+    /// Restarts the timer of one widget, after its config changed.
+    pub fn rearm(&mut self, idx: usize, period: Option<Duration>, now: Instant) {
+        if let Some(timer) = self.timers.get_mut(idx) {
+            *timer = period.map(|period| (period, now + period));
+        }
+    }
+
+    pub fn shortest(&self) -> Option<Duration> {
+        self.timers.iter().flatten().map(|(period, _)| *period).min()
+    }
+
+    /// Returns the widgets whose timer expired and re-arms them. A timer
+    /// that is late by several periods fires once, not once per period.
+    pub fn expired(&mut self, now: Instant) -> Vec<usize> {
+        let mut expired = Vec::new();
+        for (idx, timer) in self.timers.iter_mut().enumerate() {
+            if let Some((period, deadline)) = timer {
+                if now >= *deadline {
+                    *deadline += *period;
+                    if *deadline <= now {
+                        *deadline = now + *period;
+                    }
+                    expired.push(idx);
+                }
+            }
+        }
+        expired
+    }
+}
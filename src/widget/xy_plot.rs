@@ -0,0 +1,302 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use fltk::draw::Rect;
+use fltk::enums::{Color, ColorDepth, FrameType};
+use fltk::frame::Frame;
+use fltk::image::SharedImage;
+use fltk::{app, draw, prelude::*};
+use log::warn;
+use plotters::prelude::*;
+use plotters::style::Color as PlColor;
+use plotters_bitmap::{bitmap_pixel::RGBPixel, BitMapBackend};
+use serde_json::Value as Json;
+
+use crate::config::file_xml::WidgetParams;
+use crate::pubsub::topic_matcher::TopicPattern;
+use crate::pubsub::{payload_as_f64, payload_to_json};
+use crate::widget::theme::{apply_style, plot_color};
+use crate::widget::Context;
+
+use super::{PubSubWidget, WidgetMsg};
+
+const DEFAULT_SAMPLES: usize = 500;
+const MARGIN: i32 = 10;
+const X_LABEL_AREA: i32 = 30;
+const Y_LABEL_AREA: i32 = 40;
+
+/// XY scatter of the points on src, drawn as a trail that fades with age.
+/// Points are pairs of x on src and y on src_y, or come from one structured
+/// payload through the fields attribute ("x,y" or "pose.x,pose.y"), which
+/// by default takes [x, y] arrays; an array of those adds them all, as for
+/// a lidar scan. The trail holds samples points for timespan seconds. Axes
+/// are xmin..xmax and min..max, or auto-scaled. Waypoints are drawn from
+/// "x y;x y", and image is stretched over the axes as a map background.
+pub struct XyPlot {
+    cfg: WidgetParams,
+    ctx: Context,
+    frame: Option<Frame>,
+    src_y: Option<TopicPattern>,
+    fields: (String, String),
+    last_x: Option<f64>,
+    points: VecDeque<(f64, f64, Instant)>,
+    background: Option<SharedImage>,
+}
+
+impl XyPlot {
+    pub fn new(cfg: &WidgetParams) -> Self {
+        let mut plot = Self {
+            cfg: cfg.clone(),
+            ctx: Context::current(),
+            frame: None,
+            src_y: None,
+            fields: (String::new(), String::new()),
+            last_x: None,
+            points: VecDeque::new(),
+            background: None,
+        };
+        plot.configure();
+        plot
+    }
+
+    fn configure(&mut self) {
+        self.src_y = self.cfg.src_y.as_ref().and_then(|t| {
+            TopicPattern::compile(t)
+                .map_err(|e| warn!("XyPlot src_y {} : {}", t, e))
+                .ok()
+        });
+        self.fields = parse_fields(self.cfg.fields.as_deref().unwrap_or("0,1"));
+        self.background = self.cfg.image.as_ref().and_then(|file| {
+            SharedImage::load(file)
+                .map_err(|e| warn!("Cannot load map {} : {}", file, e))
+                .ok()
+        });
+    }
+
+    fn add_point(&mut self, x: f64, y: f64) {
+        self.points.push_back((x, y, Instant::now()));
+        let samples = self.cfg.max_samples.unwrap_or(DEFAULT_SAMPLES).max(1);
+        while self.points.len() > samples {
+            self.points.pop_front();
+        }
+    }
+
+    fn expire(&mut self) {
+        if let Some(secs) = self.cfg.max_timespan {
+            let timespan = Duration::from_secs(secs.max(0) as u64);
+            while self.points.front().is_some_and(|p| p.2.elapsed() > timespan) {
+                self.points.pop_front();
+            }
+        }
+    }
+
+    fn ranges(&self) -> ((f64, f64), (f64, f64)) {
+        let xs = self.points.iter().map(|p| p.0);
+        let ys = self.points.iter().map(|p| p.1);
+        let waypoints = parse_waypoints(self.cfg.waypoints.as_deref().unwrap_or(""));
+        let x = auto_range(xs.chain(waypoints.iter().map(|w| w.0)), self.cfg.xmin, self.cfg.xmax);
+        let y = auto_range(ys.chain(waypoints.iter().map(|w| w.1)), self.cfg.min, self.cfg.max);
+        (x, y)
+    }
+
+    fn render(&mut self) -> Result<(), String> {
+        let Some((w, h)) = self.frame.as_ref().map(|f| (f.w(), f.h())) else {
+            return Ok(());
+        };
+        if w <= MARGIN * 2 + Y_LABEL_AREA || h <= MARGIN * 2 + X_LABEL_AREA {
+            return Ok(());
+        }
+        let ((x_min, x_max), (y_min, y_max)) = self.ranges();
+        let bg = plot_color(self.ctx.bg(&self.cfg));
+        let fg = plot_color(self.ctx.fg(&self.cfg));
+        let trail = plot_color(self.ctx.valuator_color);
+        let target = plot_color(self.ctx.color("target", Color::from_u32(0xFF8000)));
+        let mut buf = vec![0u8; (w * h * 3) as usize];
+        for pixel in buf.chunks_mut(3) {
+            pixel.copy_from_slice(&[bg.0, bg.1, bg.2]);
+        }
+        if let Some(map) = self.background.as_ref() {
+            let area = (
+                MARGIN + Y_LABEL_AREA,
+                MARGIN,
+                w - 2 * MARGIN - Y_LABEL_AREA,
+                h - 2 * MARGIN - X_LABEL_AREA,
+            );
+            blit(&mut buf, w, map, area);
+        }
+        {
+            let drawing_area = BitMapBackend::<RGBPixel>::with_buffer_and_format(&mut buf, (w as u32, h as u32))
+                .map_err(|e| e.to_string())?
+                .into_drawing_area();
+            let mut chart = ChartBuilder::on(&drawing_area)
+                .margin(MARGIN)
+                .x_label_area_size(X_LABEL_AREA)
+                .y_label_area_size(Y_LABEL_AREA)
+                .build_cartesian_2d(x_min..x_max, y_min..y_max)
+                .map_err(|e| e.to_string())?;
+            chart
+                .configure_mesh()
+                .axis_style(&fg)
+                .label_style(("sans-serif", 12).into_font().color(&fg))
+                .bold_line_style(&fg.mix(0.2))
+                .light_line_style(&TRANSPARENT)
+                .draw()
+                .map_err(|e| e.to_string())?;
+            let waypoints = parse_waypoints(self.cfg.waypoints.as_deref().unwrap_or(""));
+            if !waypoints.is_empty() {
+                chart
+                    .draw_series(LineSeries::new(waypoints.iter().copied(), target.stroke_width(1)))
+                    .map_err(|e| e.to_string())?;
+                chart
+                    .draw_series(waypoints.iter().map(|p| Cross::new(*p, 4, target.stroke_width(2))))
+                    .map_err(|e| e.to_string())?;
+            }
+            // the oldest points are the most transparent
+            let count = self.points.len() as f64;
+            chart
+                .draw_series(self.points.iter().enumerate().map(|(idx, (x, y, _))| {
+                    let alpha = (idx + 1) as f64 / count;
+                    Circle::new((*x, *y), 2, trail.mix(alpha).filled())
+                }))
+                .map_err(|e| e.to_string())?;
+            drawing_area.present().map_err(|e| e.to_string())?;
+        }
+        match self.frame.as_mut() {
+            Some(frame) => draw::draw_rgb(frame, &buf).map_err(|e| e.to_string()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Copies the image, stretched to the area, into the RGB buffer.
+fn blit(buf: &mut [u8], buf_w: i32, image: &SharedImage, (x, y, w, h): (i32, i32, i32, i32)) {
+    let scaled = image
+        .copy_sized(w, h)
+        .to_rgb()
+        .and_then(|i| i.convert(ColorDepth::Rgb8));
+    let Ok(scaled) = scaled else {
+        return;
+    };
+    let data = scaled.to_rgb_data();
+    for row in 0..h.min(scaled.data_h()) {
+        let src = (row * w * 3) as usize;
+        let dst = (((y + row) * buf_w + x) * 3) as usize;
+        let len = (w * 3) as usize;
+        if src + len <= data.len() && dst + len <= buf.len() {
+            buf[dst..dst + len].copy_from_slice(&data[src..src + len]);
+        }
+    }
+}
+
+fn auto_range(values: impl Iterator<Item = f64>, min: Option<f64>, max: Option<f64>) -> (f64, f64) {
+    let (lo, hi) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| (lo.min(v), hi.max(v)));
+    let (lo, hi) = if lo <= hi { (lo, hi) } else { (-1.0, 1.0) };
+    let pad = ((hi - lo) * 0.05).max(1e-6);
+    let (min, max) = (min.unwrap_or(lo - pad), max.unwrap_or(hi + pad));
+    if max > min {
+        (min, max)
+    } else {
+        (min, min + 1.0)
+    }
+}
+
+// field names as JSON pointers, "pose.x" becomes "/pose/x"
+fn parse_fields(fields: &str) -> (String, String) {
+    let pointer = |f: &str| format!("/{}", f.trim().replace('.', "/"));
+    match fields.split_once(',') {
+        Some((x, y)) => (pointer(x), pointer(y)),
+        None => (pointer("0"), pointer("1")),
+    }
+}
+
+fn parse_waypoints(waypoints: &str) -> Vec<(f64, f64)> {
+    waypoints
+        .split(';')
+        .filter_map(|p| {
+            let mut coords = p.split_whitespace().map(|c| c.parse::<f64>());
+            match (coords.next(), coords.next()) {
+                (Some(Ok(x)), Some(Ok(y))) => Some((x, y)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn point(value: &Json, (x, y): &(String, String)) -> Option<(f64, f64)> {
+    Some((value.pointer(x)?.as_f64()?, value.pointer(y)?.as_f64()?))
+}
+
+impl PubSubWidget for XyPlot {
+    fn draw(&mut self) {
+        let r = self.cfg.rect;
+        let mut frame = Frame::new(r.x, r.y, r.w, r.h, None);
+        frame.set_frame(FrameType::FlatBox);
+        apply_style(&mut frame, &self.cfg, &self.ctx);
+        self.frame = Some(frame);
+        self.redraw();
+    }
+
+    fn get_config(&self) -> WidgetParams {
+        self.cfg.clone()
+    }
+
+    fn set_config(&mut self, cfg: WidgetParams) {
+        self.cfg = cfg;
+        self.configure();
+        if let Some(frame) = self.frame.as_mut() {
+            apply_style(frame, &self.cfg, &self.ctx);
+        }
+    }
+
+    fn redraw(&mut self) {
+        if let Err(e) = self.render() {
+            warn!("XyPlot {:?} : {}", self.cfg.src_topic, e);
+        }
+        self.frame.as_mut().map(|f| f.redraw());
+    }
+
+    fn subscriptions(&self) -> Vec<String> {
+        self.cfg
+            .src_topic
+            .iter()
+            .chain(self.cfg.src_y.iter())
+            .cloned()
+            .collect()
+    }
+
+    fn resize(&mut self, rect: Rect) {
+        self.cfg.rect = rect;
+        self.frame.as_mut().map(|f| f.resize(rect.x, rect.y, rect.w, rect.h));
+        self.redraw();
+    }
+
+    fn teardown(&mut self) {
+        self.frame.take().map(app::delete_widget);
+    }
+
+    fn update(&mut self, event: &WidgetMsg) {
+        match event {
+            WidgetMsg::Pub { topic, payload, .. } => {
+                if self.src_y.as_ref().is_some_and(|p| p.matches(topic)) {
+                    if let (Some(x), Ok(y)) = (self.last_x, payload_as_f64(payload)) {
+                        self.add_point(x, y);
+                    }
+                } else if self.src_y.is_some() {
+                    self.last_x = payload_as_f64(payload).ok();
+                } else if let Ok(value) = payload_to_json(payload) {
+                    let points = match point(&value, &self.fields) {
+                        Some(p) => vec![p],
+                        None => value
+                            .as_array()
+                            .map(|items| items.iter().filter_map(|i| point(i, &self.fields)).collect())
+                            .unwrap_or_default(),
+                    };
+                    for (x, y) in points {
+                        self.add_point(x, y);
+                    }
+                }
+            }
+            WidgetMsg::Tick => self.expire(),
+        }
+    }
+}